@@ -0,0 +1,160 @@
+pub mod text;
+
+use std::{cell::RefCell, rc::Rc};
+
+use anyhow::Result;
+use windows::Win32::{
+    Foundation::D2DERR_RECREATE_TARGET,
+    Graphics::{
+        Direct2D::{
+            Common::{D2D1_COLOR_F, D2D_RECT_F, D2D_SIZE_U},
+            D2D1CreateFactory, ID2D1Factory, ID2D1HwndRenderTarget, ID2D1SolidColorBrush,
+            D2D1_FACTORY_TYPE_SINGLE_THREADED, D2D1_HWND_RENDER_TARGET_PROPERTIES,
+            D2D1_RENDER_TARGET_PROPERTIES,
+        },
+        DirectWrite::{DWriteCreateFactory, IDWriteFactory, DWRITE_FACTORY_TYPE_SHARED},
+    },
+};
+
+use crate::{
+    utils::trace::log_error,
+    window::{windows::ResizeTarget, WindowsWindow},
+};
+
+pub use text::{TextAlignment, TextLayout, TextStyle};
+
+/// Direct2D drawing into a window, in place of GDI. Draw with
+/// [`D2dCanvas::draw`] on
+/// [`WindowEvent::RedrawRequested`](crate::event::WindowEvent::RedrawRequested).
+///
+/// The render target follows the window's client size and is recreated
+/// after the device was lost, e.g. by a driver update. Resources created
+/// from an older target stop working then, so create them from the
+/// [`D2dFrame`] they're drawn in.
+#[derive(Debug)]
+pub struct D2dCanvas {
+    window: WindowsWindow,
+    factory: ID2D1Factory,
+    dwrite: IDWriteFactory,
+    target: Rc<Target>,
+}
+
+#[derive(Debug, Default)]
+struct Target {
+    render_target: RefCell<Option<ID2D1HwndRenderTarget>>,
+}
+
+/// One frame of a [`D2dCanvas`], between `BeginDraw` and `EndDraw`.
+/// Coordinates are in pixels of the client area.
+#[derive(Debug)]
+pub struct D2dFrame<'a> {
+    canvas: &'a D2dCanvas,
+    render_target: &'a ID2D1HwndRenderTarget,
+}
+
+impl D2dCanvas {
+    pub fn new(window: WindowsWindow) -> Result<Self> {
+        let factory = unsafe { D2D1CreateFactory(D2D1_FACTORY_TYPE_SINGLE_THREADED, None)? };
+        let dwrite = unsafe { DWriteCreateFactory(DWRITE_FACTORY_TYPE_SHARED)? };
+        let target = Rc::new(Target::default());
+        window.add_resize_target(Rc::downgrade(&target) as _);
+        Ok(Self {
+            window,
+            factory,
+            dwrite,
+            target,
+        })
+    }
+
+    pub fn window(&self) -> WindowsWindow {
+        self.window
+    }
+
+    pub fn factory(&self) -> &ID2D1Factory {
+        &self.factory
+    }
+
+    pub fn dwrite_factory(&self) -> &IDWriteFactory {
+        &self.dwrite
+    }
+
+    /// Runs `f` with a frame to draw. When the device was lost, the target
+    /// is recreated and the window redrawn.
+    pub fn draw<T>(&self, f: impl FnOnce(&D2dFrame) -> Result<T>) -> Result<T> {
+        let render_target = self.render_target()?;
+        unsafe { render_target.BeginDraw() };
+        let output = f(&D2dFrame {
+            canvas: self,
+            render_target: &render_target,
+        });
+        match unsafe { render_target.EndDraw(None, None) } {
+            Err(e) if e.code() == D2DERR_RECREATE_TARGET => {
+                self.target.render_target.take();
+                self.window.request_redraw();
+            }
+            ended => ended?,
+        }
+        output
+    }
+
+    fn render_target(&self) -> Result<ID2D1HwndRenderTarget> {
+        let mut render_target = self.target.render_target.borrow_mut();
+        if let Some(render_target) = &*render_target {
+            return Ok(render_target.clone());
+        }
+        let (width, height) = self.window.inner_size()?;
+        let properties = D2D1_RENDER_TARGET_PROPERTIES {
+            // One unit per pixel, like the rest of the crate
+            dpiX: 96.0,
+            dpiY: 96.0,
+            ..Default::default()
+        };
+        let hwnd_properties = D2D1_HWND_RENDER_TARGET_PROPERTIES {
+            hwnd: self.window.hwnd(),
+            pixelSize: D2D_SIZE_U { width, height },
+            ..Default::default()
+        };
+        let created = unsafe {
+            self.factory
+                .CreateHwndRenderTarget(&properties, &hwnd_properties)?
+        };
+        *render_target = Some(created.clone());
+        Ok(created)
+    }
+}
+
+impl<'a> D2dFrame<'a> {
+    pub fn canvas(&self) -> &'a D2dCanvas {
+        self.canvas
+    }
+
+    /// The target to draw with, valid until the end of the frame.
+    pub fn render_target(&self) -> &'a ID2D1HwndRenderTarget {
+        self.render_target
+    }
+
+    pub fn clear(&self, color: D2D1_COLOR_F) {
+        unsafe { self.render_target.Clear(Some(&color)) };
+    }
+
+    pub fn solid_brush(&self, color: D2D1_COLOR_F) -> Result<ID2D1SolidColorBrush> {
+        Ok(unsafe { self.render_target.CreateSolidColorBrush(&color, None)? })
+    }
+
+    pub fn fill_rect(&self, rect: D2D_RECT_F, color: D2D1_COLOR_F) -> Result<()> {
+        let brush = self.solid_brush(color)?;
+        unsafe { self.render_target.FillRectangle(&rect, &brush) };
+        Ok(())
+    }
+}
+
+impl ResizeTarget for Target {
+    fn resize(&self, width: u32, height: u32) {
+        if let Some(render_target) = &*self.render_target.borrow() {
+            let resized = unsafe { render_target.Resize(&D2D_SIZE_U { width, height }) };
+            if let Err(e) = resized {
+                log_error!("Error while resizing the render target: {:?}", e);
+            }
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use anyhow::Result;
+use windows::{
+    core::{w, PCWSTR},
+    Win32::Graphics::{
+        Direct2D::{
+            Common::{D2D1_COLOR_F, D2D_POINT_2F, D2D_RECT_F},
+            D2D1_DRAW_TEXT_OPTIONS_NONE,
+        },
+        DirectWrite::{
+            IDWriteFontCollection, IDWriteTextFormat, IDWriteTextLayout,
+            DWRITE_FONT_STRETCH_NORMAL, DWRITE_FONT_STYLE_ITALIC, DWRITE_FONT_STYLE_NORMAL,
+            DWRITE_FONT_WEIGHT, DWRITE_TEXT_ALIGNMENT, DWRITE_TEXT_ALIGNMENT_CENTER,
+            DWRITE_TEXT_ALIGNMENT_LEADING, DWRITE_TEXT_ALIGNMENT_TRAILING, DWRITE_TEXT_METRICS,
+            DWRITE_WORD_WRAPPING_NO_WRAP, DWRITE_WORD_WRAPPING_WRAP,
+        },
+    },
+};
+
+use super::{D2dCanvas, D2dFrame};
+use crate::utils::strings::str_to_wstr;
+
+/// Horizontal alignment of the lines in their layout box.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextAlignment {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+impl TextAlignment {
+    fn dwrite(self) -> DWRITE_TEXT_ALIGNMENT {
+        match self {
+            TextAlignment::Left => DWRITE_TEXT_ALIGNMENT_LEADING,
+            TextAlignment::Center => DWRITE_TEXT_ALIGNMENT_CENTER,
+            TextAlignment::Right => DWRITE_TEXT_ALIGNMENT_TRAILING,
+        }
+    }
+}
+
+/// Font and paragraph settings of drawn text, 14 pixel Segoe UI wrapped
+/// at word boundaries by default.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextStyle {
+    family: String,
+    size: f32,
+    weight: u16,
+    italic: bool,
+    wrap: bool,
+    alignment: TextAlignment,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self::new("Segoe UI", 14.0)
+    }
+}
+
+impl TextStyle {
+    /// `size` is the em size in pixels. Missing families fall back to the
+    /// system's default font.
+    pub fn new(family: impl Into<String>, size: f32) -> Self {
+        Self {
+            family: family.into(),
+            size,
+            weight: 400,
+            italic: false,
+            wrap: true,
+            alignment: TextAlignment::Left,
+        }
+    }
+
+    /// Weight from 1 to 999, 400 regular and 700 bold.
+    pub fn with_weight(mut self, weight: u16) -> Self {
+        self.weight = weight.clamp(1, 999);
+        self
+    }
+
+    pub fn with_bold(self, bold: bool) -> Self {
+        self.with_weight(if bold { 700 } else { 400 })
+    }
+
+    pub fn with_italic(mut self, italic: bool) -> Self {
+        self.italic = italic;
+        self
+    }
+
+    /// Wraps lines longer than the layout box at word boundaries, on by
+    /// default.
+    pub fn with_wrap(mut self, wrap: bool) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    pub fn with_alignment(mut self, alignment: TextAlignment) -> Self {
+        self.alignment = alignment;
+        self
+    }
+}
+
+/// Text shaped and broken into lines for a box, to measure before drawing
+/// with [`D2dFrame::draw_layout`].
+#[derive(Debug, Clone)]
+pub struct TextLayout {
+    layout: IDWriteTextLayout,
+    metrics: DWRITE_TEXT_METRICS,
+}
+
+impl TextLayout {
+    pub fn layout(&self) -> &IDWriteTextLayout {
+        &self.layout
+    }
+
+    /// Size of the text itself, without trailing whitespace.
+    pub fn size(&self) -> (f32, f32) {
+        (self.metrics.width, self.metrics.height)
+    }
+
+    pub fn line_count(&self) -> u32 {
+        self.metrics.lineCount
+    }
+}
+
+impl D2dCanvas {
+    /// Lays `text` out in a box of `max_width` by `max_height` pixels. Text
+    /// overflowing the box is still laid out.
+    pub fn text_layout(
+        &self,
+        text: &str,
+        style: &TextStyle,
+        max_width: f32,
+        max_height: f32,
+    ) -> Result<TextLayout> {
+        let format = self.text_format(style)?;
+        let text: Vec<u16> = text.encode_utf16().collect();
+        let layout = unsafe {
+            self.dwrite
+                .CreateTextLayout(&text, &format, max_width, max_height)?
+        };
+        let mut metrics = DWRITE_TEXT_METRICS::default();
+        unsafe { layout.GetMetrics(&mut metrics)? };
+        Ok(TextLayout { layout, metrics })
+    }
+
+    /// Size of `text` wrapped at `max_width` pixels, `f32::INFINITY` for a
+    /// single line per paragraph.
+    pub fn measure_text(
+        &self,
+        text: &str,
+        style: &TextStyle,
+        max_width: f32,
+    ) -> Result<(f32, f32)> {
+        Ok(self
+            .text_layout(text, style, max_width, f32::INFINITY)?
+            .size())
+    }
+
+    fn text_format(&self, style: &TextStyle) -> Result<IDWriteTextFormat> {
+        let family = str_to_wstr(&style.family);
+        let font_style = if style.italic {
+            DWRITE_FONT_STYLE_ITALIC
+        } else {
+            DWRITE_FONT_STYLE_NORMAL
+        };
+        let wrapping = if style.wrap {
+            DWRITE_WORD_WRAPPING_WRAP
+        } else {
+            DWRITE_WORD_WRAPPING_NO_WRAP
+        };
+        unsafe {
+            let format = self.dwrite.CreateTextFormat(
+                PCWSTR(family.as_ptr()),
+                None::<&IDWriteFontCollection>,
+                DWRITE_FONT_WEIGHT(style.weight as i32),
+                font_style,
+                DWRITE_FONT_STRETCH_NORMAL,
+                style.size,
+                w!(""),
+            )?;
+            format.SetWordWrapping(wrapping)?;
+            format.SetTextAlignment(style.alignment.dwrite())?;
+            Ok(format)
+        }
+    }
+}
+
+impl D2dFrame<'_> {
+    /// Draws `text` laid out in `rect`. Overflowing text isn't clipped.
+    pub fn draw_text(
+        &self,
+        text: &str,
+        style: &TextStyle,
+        rect: D2D_RECT_F,
+        color: D2D1_COLOR_F,
+    ) -> Result<()> {
+        let layout =
+            self.canvas
+                .text_layout(text, style, rect.right - rect.left, rect.bottom - rect.top)?;
+        self.draw_layout(&layout, rect.left, rect.top, color)
+    }
+
+    /// Draws `layout` with the top left of its box at `x`, `y`.
+    pub fn draw_layout(
+        &self,
+        layout: &TextLayout,
+        x: f32,
+        y: f32,
+        color: D2D1_COLOR_F,
+    ) -> Result<()> {
+        let brush = self.solid_brush(color)?;
+        unsafe {
+            self.render_target.DrawTextLayout(
+                D2D_POINT_2F { x, y },
+                &layout.layout,
+                &brush,
+                D2D1_DRAW_TEXT_OPTIONS_NONE,
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,203 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use anyhow::{bail, Result};
+use windows::{
+    core::Interface,
+    Win32::{
+        Foundation::HMODULE,
+        Graphics::{
+            Direct3D::D3D_DRIVER_TYPE_HARDWARE,
+            Direct3D11::{
+                D3D11CreateDevice, ID3D11DepthStencilView, ID3D11Device, ID3D11DeviceContext,
+                ID3D11RenderTargetView, ID3D11Texture2D, D3D11_CREATE_DEVICE_BGRA_SUPPORT,
+                D3D11_CREATE_DEVICE_DEBUG, D3D11_SDK_VERSION,
+            },
+            Dxgi::{
+                Common::{DXGI_FORMAT_B8G8R8A8_UNORM, DXGI_FORMAT_UNKNOWN, DXGI_SAMPLE_DESC},
+                IDXGIDevice, IDXGIFactory2, IDXGISwapChain1, DXGI_MWA_NO_ALT_ENTER, DXGI_PRESENT,
+                DXGI_SCALING_STRETCH, DXGI_SWAP_CHAIN_DESC1, DXGI_SWAP_CHAIN_FLAG,
+                DXGI_SWAP_EFFECT_FLIP_DISCARD, DXGI_USAGE_RENDER_TARGET_OUTPUT,
+            },
+        },
+    },
+};
+
+use crate::{
+    utils::trace::log_error,
+    window::{windows::ResizeTarget, WindowsWindow},
+};
+
+/// A Direct3D 11 device with a flip model swapchain presenting to a window.
+///
+/// The back buffers follow the window's client size, resized before
+/// `WindowEvent::Resized` is dispatched. Resizing needs every reference to
+/// them released, so don't keep the view from
+/// [`render_target_view`](Self::render_target_view) past a frame.
+#[derive(Debug)]
+pub struct D3d11Swapchain {
+    window: WindowsWindow,
+    device: ID3D11Device,
+    target: Rc<Target>,
+}
+
+#[derive(Debug)]
+struct Target {
+    context: ID3D11DeviceContext,
+    swapchain: IDXGISwapChain1,
+    view: RefCell<Option<ID3D11RenderTargetView>>,
+    size: Cell<(u32, u32)>,
+}
+
+impl D3d11Swapchain {
+    /// Creates a hardware device, with the debug layer in debug builds if
+    /// installed, and a double buffered BGRA swapchain. Alt+Enter is left to
+    /// [`WindowsWindow::set_fullscreen`].
+    pub fn new(window: WindowsWindow) -> Result<Self> {
+        let (width, height) = window.inner_size()?;
+        let (device, context) =
+            create_device(cfg!(debug_assertions)).or_else(|_| create_device(false))?;
+
+        let desc = DXGI_SWAP_CHAIN_DESC1 {
+            Width: width,
+            Height: height,
+            Format: DXGI_FORMAT_B8G8R8A8_UNORM,
+            SampleDesc: DXGI_SAMPLE_DESC {
+                Count: 1,
+                Quality: 0,
+            },
+            BufferUsage: DXGI_USAGE_RENDER_TARGET_OUTPUT,
+            BufferCount: 2,
+            Scaling: DXGI_SCALING_STRETCH,
+            SwapEffect: DXGI_SWAP_EFFECT_FLIP_DISCARD,
+            ..Default::default()
+        };
+        let swapchain = unsafe {
+            let factory: IDXGIFactory2 = device.cast::<IDXGIDevice>()?.GetAdapter()?.GetParent()?;
+            let swapchain =
+                factory.CreateSwapChainForHwnd(&device, window.hwnd(), &desc, None, None)?;
+            factory.MakeWindowAssociation(window.hwnd(), DXGI_MWA_NO_ALT_ENTER)?;
+            swapchain
+        };
+
+        let target = Rc::new(Target {
+            context,
+            swapchain,
+            view: RefCell::new(None),
+            size: Cell::new((width, height)),
+        });
+        window.add_resize_target(Rc::downgrade(&target) as _);
+        Ok(Self {
+            window,
+            device,
+            target,
+        })
+    }
+
+    pub fn window(&self) -> WindowsWindow {
+        self.window
+    }
+
+    pub fn device(&self) -> &ID3D11Device {
+        &self.device
+    }
+
+    pub fn context(&self) -> &ID3D11DeviceContext {
+        &self.target.context
+    }
+
+    pub fn swapchain(&self) -> &IDXGISwapChain1 {
+        &self.target.swapchain
+    }
+
+    /// Size of the back buffers.
+    pub fn size(&self) -> (u32, u32) {
+        self.target.size.get()
+    }
+
+    /// View of the current back buffer, created on first use after a
+    /// resize.
+    pub fn render_target_view(&self) -> Result<ID3D11RenderTargetView> {
+        let mut view = self.target.view.borrow_mut();
+        if let Some(view) = &*view {
+            return Ok(view.clone());
+        }
+        let created = unsafe {
+            let buffer: ID3D11Texture2D = self.target.swapchain.GetBuffer(0)?;
+            let mut created = None;
+            self.device
+                .CreateRenderTargetView(&buffer, None, Some(&mut created))?;
+            created
+        };
+        let Some(created) = created else {
+            bail!("Could not create a view of the back buffer");
+        };
+        *view = Some(created.clone());
+        Ok(created)
+    }
+
+    /// Presents the back buffer after `sync_interval` vertical blanks, 0 to
+    /// not wait.
+    pub fn present(&self, sync_interval: u32) -> Result<()> {
+        unsafe {
+            self.target
+                .swapchain
+                .Present(sync_interval, DXGI_PRESENT(0))
+                .ok()?;
+        }
+        Ok(())
+    }
+}
+
+impl ResizeTarget for Target {
+    fn resize(&self, width: u32, height: u32) {
+        if width == 0 || height == 0 || self.size.get() == (width, height) {
+            return;
+        }
+        self.view.take();
+        unsafe {
+            self.context
+                .OMSetRenderTargets(None, None::<&ID3D11DepthStencilView>);
+            self.context.Flush();
+            let resized = self.swapchain.ResizeBuffers(
+                0,
+                width,
+                height,
+                DXGI_FORMAT_UNKNOWN,
+                DXGI_SWAP_CHAIN_FLAG(0),
+            );
+            match resized {
+                Ok(()) => self.size.set((width, height)),
+                Err(e) => log_error!("Error while resizing the swapchain: {:?}", e),
+            }
+        }
+    }
+}
+
+fn create_device(debug: bool) -> Result<(ID3D11Device, ID3D11DeviceContext)> {
+    let mut flags = D3D11_CREATE_DEVICE_BGRA_SUPPORT;
+    if debug {
+        flags |= D3D11_CREATE_DEVICE_DEBUG;
+    }
+    let mut device = None;
+    let mut context = None;
+    unsafe {
+        D3D11CreateDevice(
+            None,
+            D3D_DRIVER_TYPE_HARDWARE,
+            HMODULE::default(),
+            flags,
+            None,
+            D3D11_SDK_VERSION,
+            Some(&mut device),
+            None,
+            Some(&mut context),
+        )?;
+    }
+    match (device, context) {
+        (Some(device), Some(context)) => Ok((device, context)),
+        _ => bail!("Could not create a Direct3D 11 device"),
+    }
+}
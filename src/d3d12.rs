@@ -0,0 +1,242 @@
+use std::{cell::Cell, mem, rc::Rc, time::Duration};
+
+use anyhow::Result;
+use windows::{
+    core::Interface,
+    Win32::{
+        Foundation::{CloseHandle, BOOL, HANDLE, WAIT_OBJECT_0},
+        Graphics::{
+            Direct3D12::{
+                ID3D12CommandQueue, ID3D12Device, ID3D12Fence, ID3D12Resource,
+                D3D12_FENCE_FLAG_NONE,
+            },
+            Dxgi::{
+                Common::{DXGI_FORMAT_B8G8R8A8_UNORM, DXGI_FORMAT_UNKNOWN, DXGI_SAMPLE_DESC},
+                CreateDXGIFactory2, IDXGIFactory2, IDXGIFactory5, IDXGISwapChain3,
+                DXGI_CREATE_FACTORY_FLAGS, DXGI_FEATURE_PRESENT_ALLOW_TEARING,
+                DXGI_MWA_NO_ALT_ENTER, DXGI_PRESENT, DXGI_PRESENT_ALLOW_TEARING,
+                DXGI_SCALING_STRETCH, DXGI_SWAP_CHAIN_DESC1, DXGI_SWAP_CHAIN_FLAG,
+                DXGI_SWAP_CHAIN_FLAG_ALLOW_TEARING,
+                DXGI_SWAP_CHAIN_FLAG_FRAME_LATENCY_WAITABLE_OBJECT, DXGI_SWAP_EFFECT_FLIP_DISCARD,
+                DXGI_USAGE_RENDER_TARGET_OUTPUT,
+            },
+        },
+        System::Threading::{WaitForSingleObjectEx, INFINITE},
+    },
+};
+
+use crate::{
+    utils::trace::log_error,
+    window::{windows::ResizeTarget, WindowsWindow},
+};
+
+/// A flip model DXGI swapchain presenting a Direct3D 12 queue's work to a
+/// window.
+///
+/// The back buffers follow the window's client size, resized before
+/// `WindowEvent::Resized` is dispatched once the queue is idle. Resizing
+/// needs every reference to them released, so fetch them with
+/// [`buffer`](Self::buffer) again after a resize rather than keeping them.
+#[derive(Debug)]
+pub struct D3d12Swapchain {
+    window: WindowsWindow,
+    target: Rc<Target>,
+    frame_latency: HANDLE,
+}
+
+#[derive(Debug)]
+struct Target {
+    queue: ID3D12CommandQueue,
+    fence: ID3D12Fence,
+    fence_value: Cell<u64>,
+    swapchain: IDXGISwapChain3,
+    flags: DXGI_SWAP_CHAIN_FLAG,
+    size: Cell<(u32, u32)>,
+}
+
+impl D3d12Swapchain {
+    /// Creates a BGRA swapchain of `buffer_count` buffers, at least 2, for
+    /// `queue`, allowing tearing when the system supports it. Alt+Enter is
+    /// left to [`WindowsWindow::set_fullscreen`].
+    pub fn new(
+        window: WindowsWindow,
+        queue: &ID3D12CommandQueue,
+        buffer_count: u32,
+    ) -> Result<Self> {
+        let (width, height) = window.inner_size()?;
+        let factory: IDXGIFactory2 = unsafe { CreateDXGIFactory2(DXGI_CREATE_FACTORY_FLAGS(0))? };
+        let mut flags = DXGI_SWAP_CHAIN_FLAG_FRAME_LATENCY_WAITABLE_OBJECT;
+        if tearing_supported(&factory) {
+            flags |= DXGI_SWAP_CHAIN_FLAG_ALLOW_TEARING;
+        }
+
+        let desc = DXGI_SWAP_CHAIN_DESC1 {
+            Width: width,
+            Height: height,
+            Format: DXGI_FORMAT_B8G8R8A8_UNORM,
+            SampleDesc: DXGI_SAMPLE_DESC {
+                Count: 1,
+                Quality: 0,
+            },
+            BufferUsage: DXGI_USAGE_RENDER_TARGET_OUTPUT,
+            BufferCount: buffer_count.max(2),
+            Scaling: DXGI_SCALING_STRETCH,
+            SwapEffect: DXGI_SWAP_EFFECT_FLIP_DISCARD,
+            Flags: flags.0 as u32,
+            ..Default::default()
+        };
+        let (swapchain, fence, frame_latency) = unsafe {
+            let swapchain: IDXGISwapChain3 = factory
+                .CreateSwapChainForHwnd(queue, window.hwnd(), &desc, None, None)?
+                .cast()?;
+            factory.MakeWindowAssociation(window.hwnd(), DXGI_MWA_NO_ALT_ENTER)?;
+            let mut device: Option<ID3D12Device> = None;
+            queue.GetDevice(&mut device)?;
+            let fence: ID3D12Fence = device
+                .ok_or_else(|| anyhow::anyhow!("The queue has no device"))?
+                .CreateFence(0, D3D12_FENCE_FLAG_NONE)?;
+            let frame_latency = swapchain.GetFrameLatencyWaitableObject();
+            (swapchain, fence, frame_latency)
+        };
+
+        let target = Rc::new(Target {
+            queue: queue.clone(),
+            fence,
+            fence_value: Cell::new(0),
+            swapchain,
+            flags,
+            size: Cell::new((width, height)),
+        });
+        window.add_resize_target(Rc::downgrade(&target) as _);
+        Ok(Self {
+            window,
+            target,
+            frame_latency,
+        })
+    }
+
+    pub fn window(&self) -> WindowsWindow {
+        self.window
+    }
+
+    pub fn swapchain(&self) -> &IDXGISwapChain3 {
+        &self.target.swapchain
+    }
+
+    /// Size of the back buffers.
+    pub fn size(&self) -> (u32, u32) {
+        self.target.size.get()
+    }
+
+    /// Whether presenting without vsync may tear, for variable refresh rate
+    /// displays.
+    pub fn tearing_supported(&self) -> bool {
+        self.target
+            .flags
+            .contains(DXGI_SWAP_CHAIN_FLAG_ALLOW_TEARING)
+    }
+
+    pub fn current_buffer_index(&self) -> u32 {
+        unsafe { self.target.swapchain.GetCurrentBackBufferIndex() }
+    }
+
+    /// Back buffer `index`, to create render target views of.
+    pub fn buffer(&self, index: u32) -> Result<ID3D12Resource> {
+        Ok(unsafe { self.target.swapchain.GetBuffer(index)? })
+    }
+
+    /// Frames queued before [`wait_for_frame`](Self::wait_for_frame) blocks,
+    /// 1 by default for the lowest latency.
+    pub fn set_maximum_frame_latency(&self, frames: u32) -> Result<()> {
+        unsafe { self.target.swapchain.SetMaximumFrameLatency(frames)? };
+        Ok(())
+    }
+
+    /// Waits until a frame can be queued without blocking in `present`,
+    /// before sampling input for it. `false` on timeout.
+    pub fn wait_for_frame(&self, timeout: Option<Duration>) -> bool {
+        // Long timeouts saturate short of INFINITE, rather than wrapping
+        let timeout = timeout.map_or(INFINITE, |timeout| {
+            u32::try_from(timeout.as_millis()).map_or(INFINITE - 1, |ms| ms.min(INFINITE - 1))
+        });
+        unsafe { WaitForSingleObjectEx(self.frame_latency, timeout, true) == WAIT_OBJECT_0 }
+    }
+
+    /// Presents the current back buffer after `sync_interval` vertical
+    /// blanks. With 0 it tears rather than waits where supported.
+    pub fn present(&self, sync_interval: u32) -> Result<()> {
+        let flags =
+            if sync_interval == 0 && self.tearing_supported() && !self.is_exclusive_fullscreen() {
+                DXGI_PRESENT_ALLOW_TEARING
+            } else {
+                DXGI_PRESENT(0)
+            };
+        unsafe { self.target.swapchain.Present(sync_interval, flags).ok()? };
+        Ok(())
+    }
+
+    fn is_exclusive_fullscreen(&self) -> bool {
+        let mut fullscreen = BOOL::default();
+        unsafe {
+            let _ = self
+                .target
+                .swapchain
+                .GetFullscreenState(Some(&mut fullscreen), None);
+        }
+        fullscreen.as_bool()
+    }
+}
+
+impl Drop for D3d12Swapchain {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CloseHandle(self.frame_latency);
+        }
+    }
+}
+
+impl Target {
+    /// Blocks until the queue has executed everything submitted so far.
+    fn wait_for_queue(&self) -> Result<()> {
+        let value = self.fence_value.get() + 1;
+        self.fence_value.set(value);
+        unsafe {
+            self.queue.Signal(&self.fence, value)?;
+            // Without an event it returns once the fence is reached
+            self.fence.SetEventOnCompletion(value, HANDLE::default())?;
+        }
+        Ok(())
+    }
+}
+
+impl ResizeTarget for Target {
+    fn resize(&self, width: u32, height: u32) {
+        if width == 0 || height == 0 || self.size.get() == (width, height) {
+            return;
+        }
+        let resized = self.wait_for_queue().and_then(|()| unsafe {
+            self.swapchain
+                .ResizeBuffers(0, width, height, DXGI_FORMAT_UNKNOWN, self.flags)
+                .map_err(Into::into)
+        });
+        match resized {
+            Ok(()) => self.size.set((width, height)),
+            Err(e) => log_error!("Error while resizing the swapchain: {:?}", e),
+        }
+    }
+}
+
+fn tearing_supported(factory: &IDXGIFactory2) -> bool {
+    let Ok(factory) = factory.cast::<IDXGIFactory5>() else {
+        return false;
+    };
+    let mut allowed = BOOL::default();
+    let checked = unsafe {
+        factory.CheckFeatureSupport(
+            DXGI_FEATURE_PRESENT_ALLOW_TEARING,
+            &mut allowed as *mut _ as *mut _,
+            mem::size_of::<BOOL>() as u32,
+        )
+    };
+    checked.is_ok() && allowed.as_bool()
+}
@@ -0,0 +1,387 @@
+use std::time::Duration;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use windows::Win32::Foundation::HANDLE;
+
+use crate::{
+    gamepad::{GamepadAxis, GamepadButton, GamepadId},
+    hotkey::HotkeyId,
+    keyboard::{HookedKey, KeyCode, KeyboardLayout, ModifiersState},
+    window::WindowId,
+};
+
+/// An event delivered by the event loop.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Event<T = ()> {
+    /// Something happened to one of the application's windows.
+    WindowEvent {
+        window_id: WindowId,
+        event: WindowEvent,
+    },
+    /// An event sent through an [`EventLoopProxy`](crate::event_loop::EventLoopProxy).
+    UserEvent(T),
+    /// The handle registered at this index with
+    /// [`EventLoop::register_handle`](crate::event_loop::EventLoop::register_handle)
+    /// was signaled.
+    HandleSignaled(usize),
+    /// The message queue has been drained and the loop is about to wait
+    /// according to its [`ControlFlow`](crate::event_loop::ControlFlow).
+    /// Handy for per-iteration work like stepping animations.
+    AboutToWait,
+    /// Input straight from a device, see
+    /// [`WindowsWindow::set_raw_keyboard`](crate::window::WindowsWindow::set_raw_keyboard)
+    /// and [`WindowsWindow::set_raw_mouse`](crate::window::WindowsWindow::set_raw_mouse).
+    DeviceEvent {
+        device_id: DeviceId,
+        event: DeviceEvent,
+    },
+    /// The compositor presented a frame, delivered when
+    /// [`EventLoop::set_frame_events`](crate::event_loop::EventLoop::set_frame_events)
+    /// is enabled. Render the next frame now.
+    Frame {
+        /// Time between two refreshes of the display, if DWM reports it.
+        refresh_period: Option<Duration>,
+    },
+    /// A controller changed, delivered when
+    /// [`EventLoop::set_gamepad_polling`](crate::event_loop::EventLoop::set_gamepad_polling)
+    /// is enabled.
+    Gamepad { id: GamepadId, event: GamepadEvent },
+    /// A hotkey registered with a
+    /// [`HotkeyManager`](crate::hotkey::HotkeyManager) was pressed.
+    GlobalHotkey(HotkeyId),
+    /// A key went through the
+    /// [`KeyboardHook`](crate::keyboard::KeyboardHook) of the loop's
+    /// thread, whether its filter swallowed it or not.
+    HookedKey(HookedKey),
+}
+
+impl<T> Event<T> {
+    /// Changes the user event type, handing the event back if it is a
+    /// [`Event::UserEvent`].
+    pub fn map_nonuser_event<U>(self) -> Result<Event<U>, Event<T>> {
+        match self {
+            Event::WindowEvent { window_id, event } => Ok(Event::WindowEvent { window_id, event }),
+            Event::UserEvent(_) => Err(self),
+            Event::HandleSignaled(index) => Ok(Event::HandleSignaled(index)),
+            Event::DeviceEvent { device_id, event } => Ok(Event::DeviceEvent { device_id, event }),
+            Event::AboutToWait => Ok(Event::AboutToWait),
+            Event::Frame { refresh_period } => Ok(Event::Frame { refresh_period }),
+            Event::Gamepad { id, event } => Ok(Event::Gamepad { id, event }),
+            Event::GlobalHotkey(id) => Ok(Event::GlobalHotkey(id)),
+            Event::HookedKey(key) => Ok(Event::HookedKey(key)),
+        }
+    }
+}
+
+/// Identifies an input device, stable while it stays connected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DeviceId(isize);
+
+impl From<HANDLE> for DeviceId {
+    fn from(handle: HANDLE) -> Self {
+        Self(handle.0 as isize)
+    }
+}
+
+/// Raw input of a device, independent of any window.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DeviceEvent {
+    Key {
+        physical_key: KeyCode,
+        state: ElementState,
+    },
+    /// Relative movement in mouse units, which don't match pixels.
+    MouseMotion { dx: i32, dy: i32 },
+    /// An input report of a HID device, see
+    /// [`WindowsWindow::set_raw_hid`](crate::window::WindowsWindow::set_raw_hid).
+    /// Starts with the report id if the device uses them, the rest is laid
+    /// out as its report descriptor says.
+    HidReport(Vec<u8>),
+    /// The device was plugged in, or was already there when its kind got
+    /// registered.
+    Added(DeviceKind),
+    /// The device was unplugged.
+    Removed,
+}
+
+/// What a device is, see [`DeviceEvent::Added`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DeviceKind {
+    Mouse,
+    Keyboard,
+    /// Any other HID device, by its top level usage, e.g. page 0x0d for pens
+    /// and drawing tablets.
+    Hid {
+        vendor_id: u32,
+        product_id: u32,
+        usage_page: u16,
+        usage: u16,
+    },
+}
+
+/// Change of an XInput controller. A controller connected when polling
+/// starts is reported as connecting, followed by what it is holding.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum GamepadEvent {
+    Connected,
+    Disconnected,
+    Button {
+        button: GamepadButton,
+        state: ElementState,
+    },
+    /// The axis moved to `value`, see [`GamepadAxis`] for its range.
+    Axis {
+        axis: GamepadAxis,
+        value: f32,
+    },
+}
+
+/// A window event, translated from the raw messages the window procedure receives.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum WindowEvent {
+    /// The client area changed size.
+    Resized {
+        width: u32,
+        height: u32,
+    },
+    /// The client area moved, in screen coordinates.
+    Moved {
+        x: i32,
+        y: i32,
+    },
+    /// The window was minimized, e.g. to pause rendering until it is restored.
+    Minimized,
+    Maximized,
+    /// The window left the minimized or maximized state.
+    Restored,
+    /// The user asked to close the window (close button, Alt+F4, ...). The
+    /// window is destroyed afterwards unless the handler calls
+    /// [`WindowsWindow::veto_close`](crate::window::WindowsWindow::veto_close).
+    CloseRequested,
+    /// The window was destroyed.
+    Destroyed,
+    /// The window needs to be redrawn.
+    RedrawRequested,
+    /// The timer started with this id by
+    /// [`WindowsWindow::set_timer`](crate::window::WindowsWindow::set_timer) elapsed.
+    Timer(usize),
+    /// The window gained (`true`) or lost (`false`) keyboard focus.
+    Focused(bool),
+    /// The window was enabled (`true`) or disabled (`false`), see
+    /// [`WindowsWindow::set_enabled`](crate::window::WindowsWindow::set_enabled).
+    Enabled(bool),
+    KeyboardInput {
+        /// Position of the key, whatever the layout.
+        physical_key: KeyCode,
+        /// The key as labelled in the active layout.
+        logical_key: KeyCode,
+        state: ElementState,
+        /// Sent again while the key is held down.
+        repeat: bool,
+    },
+    MouseInput {
+        button: MouseButton,
+        state: ElementState,
+    },
+    /// The user switched the input language or keyboard layout, e.g. to
+    /// remap shortcuts bound to key labels.
+    KeyboardLayoutChanged(KeyboardLayout),
+    /// Text composed with an input method, see
+    /// [`WindowsWindow::set_ime_events`](crate::window::WindowsWindow::set_ime_events).
+    Ime(Ime),
+    /// Shift, Ctrl, Alt or Windows was pressed or released, or the window
+    /// lost the focus with some of them held.
+    ModifiersChanged(ModifiersState),
+    /// A character was typed, after the layout, dead keys and Shift or
+    /// AltGr were applied. Control characters like `'\r'` and `'\u{8}'` are
+    /// included.
+    ReceivedCharacter(char),
+    /// The second press of a double click, delivered after its
+    /// [`WindowEvent::MouseInput`]. Uses the double-click time and distance
+    /// of the user's settings.
+    DoubleClick {
+        button: MouseButton,
+    },
+    /// The window lost the mouse capture, see
+    /// [`WindowsWindow::set_capture`](crate::window::WindowsWindow::set_capture).
+    /// Cancel the drag operation it was for.
+    CaptureLost,
+    /// The cursor entered the client area, delivered before its first
+    /// [`WindowEvent::CursorMoved`].
+    CursorEntered,
+    /// The cursor left the client area, e.g. to clear hover states.
+    CursorLeft,
+    /// The cursor moved over the client area, in client coordinates and
+    /// physical pixels, see
+    /// [`WindowsWindow::scale_factor`](crate::window::WindowsWindow::scale_factor).
+    CursorMoved {
+        x: i32,
+        y: i32,
+    },
+    /// The user turned the mouse wheel or scrolled on a touchpad.
+    MouseWheel(ScrollDelta),
+    /// The user switched the system between light and dark mode.
+    ThemeChanged(Theme),
+    /// A finger touched, moved on or left a touch screen over the window.
+    /// Windows also turns the first finger into mouse input.
+    Touch(Touch),
+    /// A pen touched, moved over or left the screen. Windows also turns it
+    /// into mouse input.
+    Pen(Pen),
+    /// A touch gesture, see
+    /// [`WindowsWindow::set_gestures`](crate::window::WindowsWindow::set_gestures).
+    Gesture(Gesture),
+}
+
+/// A finger on a touch screen, see [`WindowEvent::Touch`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Touch {
+    /// Tells fingers apart, from [`TouchPhase::Started`] until the finger
+    /// ends or is cancelled. Ids are reused afterwards.
+    pub id: u32,
+    pub phase: TouchPhase,
+    /// Position in client coordinates and physical pixels.
+    pub x: i32,
+    pub y: i32,
+    /// Width and height of the contact area in physical pixels, if the
+    /// screen reports it.
+    pub contact_size: Option<(u32, u32)>,
+}
+
+/// Change of a touch gesture since its previous event.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Gesture {
+    /// The fingers moved together, by physical pixels.
+    Pan { dx: i32, dy: i32 },
+    /// The fingers moved apart (`scale` above 1) or closer, around a point
+    /// in client coordinates.
+    PinchZoom { scale: f64, x: i32, y: i32 },
+    /// The fingers turned by `angle` radians around a point in client
+    /// coordinates.
+    Rotate { angle: f64, x: i32, y: i32 },
+}
+
+/// A pen or stylus, see [`WindowEvent::Pen`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Pen {
+    pub id: u32,
+    /// [`TouchPhase::Moved`] is also sent while the pen hovers over the
+    /// screen, with `in_contact` unset.
+    pub phase: TouchPhase,
+    /// Position in client coordinates and physical pixels.
+    pub x: i32,
+    pub y: i32,
+    pub in_contact: bool,
+    /// From 0 to 1, if the pen reports it.
+    pub pressure: Option<f32>,
+    /// Angle from the perpendicular towards the right and the bottom of the
+    /// screen, in degrees from -90 to 90. Zero if the pen doesn't report it.
+    pub tilt: (i32, i32),
+    /// Clockwise rotation around its own axis in degrees, if the pen
+    /// reports it.
+    pub rotation: Option<u32>,
+    /// The barrel button is held.
+    pub barrel: bool,
+    /// The pen is turned over, touching or hovering with its eraser end.
+    pub eraser: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TouchPhase {
+    Started,
+    Moved,
+    Ended,
+    /// The system took the touch over, e.g. for an edge swipe. Undo what
+    /// the finger did.
+    Cancelled,
+}
+
+/// Progress of a composition in an input method editor.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Ime {
+    /// The text being composed changed, to draw in place of the cursor. An
+    /// empty text ends the composition.
+    Preedit {
+        text: String,
+        /// Byte offset of the cursor in `text`.
+        cursor: Option<usize>,
+    },
+    /// The user accepted this text, to insert.
+    Commit(String),
+}
+
+/// Distance scrolled by [`WindowEvent::MouseWheel`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ScrollDelta {
+    /// Horizontal rotation in wheel units, positive to the right. A notch
+    /// of a classic wheel is 120, touchpads send less at a time.
+    pub x: i32,
+    /// Vertical rotation in wheel units, positive away from the user.
+    pub y: i32,
+    /// Lines to scroll per notch in the user's settings, `None` to scroll a
+    /// screen per notch instead.
+    pub lines_per_notch: Option<u32>,
+    /// Characters to scroll per notch horizontally.
+    pub chars_per_notch: u32,
+}
+
+impl ScrollDelta {
+    /// Wheel units of a notch.
+    pub const NOTCH: i32 = 120;
+
+    /// Characters and lines to scroll by, fractional for touchpads. Scrolls
+    /// by a line per notch if the user asked for screens.
+    pub fn lines(&self) -> (f32, f32) {
+        let notches = |delta: i32| delta as f32 / Self::NOTCH as f32;
+        (
+            notches(self.x) * self.chars_per_notch as f32,
+            notches(self.y) * self.lines_per_notch.unwrap_or(1) as f32,
+        )
+    }
+
+    /// Pixels to scroll by for content with lines `line_height` pixels high
+    /// and characters `char_width` pixels wide.
+    pub fn pixels(&self, char_width: f32, line_height: f32) -> (f32, f32) {
+        let (chars, lines) = self.lines();
+        (chars * char_width, lines * line_height)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Theme {
+    Light,
+    Dark,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ElementState {
+    Pressed,
+    Released,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+    /// The first extra button, usually navigating back.
+    Back,
+    /// The second extra button, usually navigating forward.
+    Forward,
+}
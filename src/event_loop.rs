@@ -0,0 +1,40 @@
+use std::time::Instant;
+
+pub mod game_loop;
+pub mod metrics;
+pub mod spawn;
+#[cfg(feature = "async")]
+pub mod stream;
+pub mod windows;
+
+pub use game_loop::{GameEvent, GameLoop};
+pub use metrics::{LatencyHistogram, Metrics};
+pub use spawn::{SpawnedEventLoop, WindowController};
+#[cfg(feature = "async")]
+pub use stream::EventStream;
+pub use windows::{event_time, EventLoop, EventLoopProxy};
+
+/// How the event loop waits once it has drained the pending messages.
+///
+/// The event handler can change it while handling any event; the new value
+/// applies from the next iteration on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ControlFlow {
+    /// Never block, iterate again as soon as the queue is empty.
+    Poll,
+    /// Block until a new message arrives.
+    #[default]
+    Wait,
+    /// Block until a new message arrives or the deadline passes. Backed by a
+    /// high resolution timer where the OS has one, accurate to about 1 ms.
+    WaitUntil(Instant),
+}
+
+/// Result of a single [`EventLoop::pump_events`] call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PumpStatus {
+    /// The queue was drained, keep pumping.
+    Continue,
+    /// `WM_QUIT` was received with this exit code.
+    Exit(i32),
+}
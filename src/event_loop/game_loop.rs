@@ -0,0 +1,120 @@
+use std::time::{Duration, Instant};
+
+use super::{EventLoop, PumpStatus};
+use crate::event::Event;
+
+/// What a [`GameLoop`] hands to its handler.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameEvent<T = ()> {
+    /// An event of the underlying [`EventLoop`].
+    Event(Event<T>),
+    /// Advance the simulation by exactly this timestep.
+    Update(Duration),
+    /// Draw a frame. The factor in `0.0..1.0` is how far the current time is
+    /// between the last two simulation states, for interpolating between them.
+    Render(f64),
+}
+
+/// Fixed timestep game loop on top of [`EventLoop::pump_events`]: the
+/// simulation advances in constant steps however long frames take, while
+/// rendering happens once per iteration.
+#[derive(Debug, Clone, Copy)]
+pub struct GameLoop {
+    timestep: Duration,
+    max_frame_time: Duration,
+}
+
+impl GameLoop {
+    pub fn new(timestep: Duration) -> Self {
+        Self {
+            timestep,
+            max_frame_time: Duration::from_millis(250),
+        }
+    }
+
+    /// Caps the time simulated after a slow frame (250 ms by default), so the
+    /// simulation can't fall further and further behind.
+    pub fn with_max_frame_time(mut self, max_frame_time: Duration) -> Self {
+        self.max_frame_time = max_frame_time;
+        self
+    }
+
+    /// Runs until `WM_QUIT` is received and returns its exit code. Each
+    /// iteration delivers the pending events, then as many
+    /// [`GameEvent::Update`]s as the elapsed time calls for, then one
+    /// [`GameEvent::Render`].
+    pub fn run<T, F>(&self, event_loop: &mut EventLoop<T>, mut handler: F) -> i32
+    where
+        T: 'static,
+        F: FnMut(GameEvent<T>),
+    {
+        let mut accumulator = Accumulator::new(self.timestep, self.max_frame_time);
+        let mut last = Instant::now();
+        loop {
+            let status = event_loop.pump_events(Some(Duration::ZERO), |event, _, _| {
+                handler(GameEvent::Event(event))
+            });
+            if let PumpStatus::Exit(exit_code) = status {
+                return exit_code;
+            }
+
+            let now = Instant::now();
+            let (steps, alpha) = accumulator.advance(now - last);
+            last = now;
+            for _ in 0..steps {
+                handler(GameEvent::Update(self.timestep));
+            }
+            handler(GameEvent::Render(alpha));
+        }
+    }
+}
+
+struct Accumulator {
+    timestep: Duration,
+    max_frame_time: Duration,
+    accumulated: Duration,
+}
+
+impl Accumulator {
+    fn new(timestep: Duration, max_frame_time: Duration) -> Self {
+        Self {
+            timestep,
+            max_frame_time,
+            accumulated: Duration::ZERO,
+        }
+    }
+
+    /// Adds a frame's time, returns the steps to simulate and the
+    /// interpolation factor left over.
+    fn advance(&mut self, frame_time: Duration) -> (u32, f64) {
+        if self.timestep.is_zero() {
+            return (1, 0.0);
+        }
+        self.accumulated += frame_time.min(self.max_frame_time);
+        let mut steps = 0;
+        while self.accumulated >= self.timestep {
+            self.accumulated -= self.timestep;
+            steps += 1;
+        }
+        (
+            steps,
+            self.accumulated.as_secs_f64() / self.timestep.as_secs_f64(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accumulates_fixed_steps() {
+        let step = Duration::from_millis(10);
+        let mut accumulator = Accumulator::new(step, Duration::from_millis(100));
+
+        assert_eq!(accumulator.advance(Duration::from_millis(25)), (2, 0.5));
+        assert_eq!(accumulator.advance(Duration::from_millis(5)), (1, 0.0));
+        // Slow frames are capped
+        assert_eq!(accumulator.advance(Duration::from_secs(1)), (10, 0.0));
+    }
+}
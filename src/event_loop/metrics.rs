@@ -0,0 +1,127 @@
+use std::time::Duration;
+
+/// Number of latency buckets, each twice as wide as the previous one.
+const LATENCY_BUCKETS: usize = 8;
+
+/// Counters describing how busy the event loop is, see
+/// [`EventLoop::metrics`](super::EventLoop::metrics).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Metrics {
+    /// Time covered by these numbers, since the loop was created or the
+    /// metrics were last reset.
+    pub elapsed: Duration,
+    /// Messages taken off the queue by the loop.
+    pub messages: u64,
+    /// Events delivered to the handler.
+    pub events: u64,
+    /// Time spent inside the handler.
+    pub callback_time: Duration,
+    /// Longest single handler call.
+    pub longest_callback: Duration,
+    /// Time from a message being queued to the loop dispatching it.
+    pub dispatch_latency: LatencyHistogram,
+}
+
+impl Metrics {
+    pub(crate) const fn new() -> Self {
+        Self {
+            elapsed: Duration::ZERO,
+            messages: 0,
+            events: 0,
+            callback_time: Duration::ZERO,
+            longest_callback: Duration::ZERO,
+            dispatch_latency: LatencyHistogram::new(),
+        }
+    }
+
+    pub fn messages_per_second(&self) -> f64 {
+        per_second(self.messages, self.elapsed)
+    }
+
+    pub fn events_per_second(&self) -> f64 {
+        per_second(self.events, self.elapsed)
+    }
+
+    pub fn mean_callback_time(&self) -> Duration {
+        match u32::try_from(self.events) {
+            Ok(0) => Duration::ZERO,
+            Ok(events) => self.callback_time / events,
+            Err(_) => self.callback_time.div_f64(self.events as f64),
+        }
+    }
+
+    pub(crate) fn record_callback(&mut self, duration: Duration) {
+        self.events += 1;
+        self.callback_time += duration;
+        self.longest_callback = self.longest_callback.max(duration);
+    }
+}
+
+fn per_second(count: u64, elapsed: Duration) -> f64 {
+    if elapsed.is_zero() {
+        0.0
+    } else {
+        count as f64 / elapsed.as_secs_f64()
+    }
+}
+
+/// Histogram of durations in power-of-two millisecond buckets: under 1 ms,
+/// under 2 ms, under 4 ms, ... and a last bucket for everything slower.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LatencyHistogram {
+    counts: [u64; LATENCY_BUCKETS],
+    max: Duration,
+}
+
+impl LatencyHistogram {
+    pub(crate) const fn new() -> Self {
+        Self {
+            counts: [0; LATENCY_BUCKETS],
+            max: Duration::ZERO,
+        }
+    }
+
+    pub(crate) fn record(&mut self, latency: Duration) {
+        let millis = latency.as_millis();
+        let bucket = (0..LATENCY_BUCKETS - 1)
+            .find(|bucket| millis < 1 << bucket)
+            .unwrap_or(LATENCY_BUCKETS - 1);
+        self.counts[bucket] += 1;
+        self.max = self.max.max(latency);
+    }
+
+    /// Upper bound of every bucket (`None` for the last, unbounded one) with
+    /// its sample count.
+    pub fn buckets(&self) -> impl Iterator<Item = (Option<Duration>, u64)> + '_ {
+        self.counts.iter().enumerate().map(|(bucket, count)| {
+            let bound = (bucket < LATENCY_BUCKETS - 1).then(|| Duration::from_millis(1 << bucket));
+            (bound, *count)
+        })
+    }
+
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    pub fn max(&self) -> Duration {
+        self.max
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets_latencies_by_power_of_two() {
+        let mut histogram = LatencyHistogram::new();
+        for millis in [0, 1, 3, 3, 500] {
+            histogram.record(Duration::from_millis(millis));
+        }
+
+        let counts: Vec<u64> = histogram.buckets().map(|(_, count)| count).collect();
+        assert_eq!(counts, [1, 1, 2, 0, 0, 0, 0, 1]);
+        assert_eq!(histogram.count(), 5);
+        assert_eq!(histogram.max(), Duration::from_millis(500));
+    }
+}
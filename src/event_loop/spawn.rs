@@ -0,0 +1,156 @@
+use std::{
+    sync::mpsc::{self, Receiver, Sender},
+    thread::{self, JoinHandle},
+};
+
+use anyhow::{anyhow, bail, Result};
+use windows::Win32::{
+    Foundation::{LPARAM, WPARAM},
+    Graphics::Gdi::InvalidateRect,
+    UI::WindowsAndMessaging::{PostMessageW, WM_CLOSE},
+};
+
+use super::{EventLoop, EventLoopProxy};
+use crate::{event::Event, window::windows::post_quit_message, window::WindowId};
+
+type Task = Box<dyn FnOnce() + Send>;
+
+/// What a [`WindowController`] asks of the event loop thread.
+enum Command<T> {
+    User(T),
+    Run(Task),
+    Exit(i32),
+}
+
+/// An event loop running on its own thread, see [`EventLoop::spawn`].
+pub struct SpawnedEventLoop<T: Send + 'static> {
+    /// Every event the loop delivers, in order.
+    pub events: Receiver<Event<T>>,
+    pub controller: WindowController<T>,
+    thread: JoinHandle<i32>,
+}
+
+impl<T: Send + 'static> SpawnedEventLoop<T> {
+    /// Waits for the loop to quit and returns its exit code.
+    pub fn join(self) -> Result<i32> {
+        self.thread
+            .join()
+            .map_err(|_| anyhow!("The event loop thread panicked"))
+    }
+}
+
+/// Drives the windows of a spawned event loop from any thread.
+pub struct WindowController<T: 'static> {
+    proxy: EventLoopProxy<Command<T>>,
+}
+
+impl<T: Send + 'static> WindowController<T> {
+    /// Delivers `event` as an [`Event::UserEvent`] on the events channel.
+    pub fn send_event(&self, event: T) -> Result<()> {
+        self.proxy.send_event(Command::User(event))
+    }
+
+    /// Runs `f` on the event loop thread, where windows can be created and
+    /// used.
+    pub fn run_on_loop<F>(&self, f: F) -> Result<()>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        self.proxy.send_event(Command::Run(Box::new(f)))
+    }
+
+    /// Asks the window to close, as if the user clicked its close button.
+    pub fn close(&self, window_id: WindowId) -> Result<()> {
+        if let Err(e) = unsafe { PostMessageW(window_id.hwnd(), WM_CLOSE, WPARAM(0), LPARAM(0)) } {
+            bail!("Could not close the window: {:?}", e);
+        }
+        Ok(())
+    }
+
+    pub fn request_redraw(&self, window_id: WindowId) {
+        unsafe {
+            let _ = InvalidateRect(window_id.hwnd(), None, false);
+        }
+    }
+
+    /// Quits the event loop with `exit_code`, leaving its windows to be
+    /// destroyed with the thread.
+    pub fn exit(&self, exit_code: i32) -> Result<()> {
+        self.proxy.send_event(Command::Exit(exit_code))
+    }
+}
+
+impl<T: 'static> Clone for WindowController<T> {
+    fn clone(&self) -> Self {
+        Self {
+            proxy: self.proxy.clone(),
+        }
+    }
+}
+
+impl<T: Send + 'static> EventLoop<T> {
+    /// Runs an event loop on a new thread, so a GUI can be added to a program
+    /// whose main thread is busy with something else. `setup` runs on that
+    /// thread first and creates the initial windows; events come back through
+    /// [`SpawnedEventLoop::events`].
+    ///
+    /// The loop quits like [`EventLoop::run`] does, when its last window is
+    /// destroyed, or through [`WindowController::exit`].
+    pub fn spawn<F>(setup: F) -> Result<SpawnedEventLoop<T>>
+    where
+        F: FnOnce() -> Result<()> + Send + 'static,
+    {
+        let (events_sender, events) = mpsc::channel();
+        let (proxy_sender, proxy_receiver) = mpsc::channel();
+        let thread = thread::Builder::new()
+            .name("paneless event loop".into())
+            .spawn(move || run_spawned(setup, events_sender, proxy_sender))?;
+
+        match proxy_receiver.recv() {
+            Ok(Ok(proxy)) => Ok(SpawnedEventLoop {
+                events,
+                controller: WindowController { proxy },
+                thread,
+            }),
+            Ok(Err(e)) => Err(e),
+            Err(_) => bail!("The event loop thread panicked during setup"),
+        }
+    }
+}
+
+fn run_spawned<T, F>(
+    setup: F,
+    events: Sender<Event<T>>,
+    proxy_sender: Sender<Result<EventLoopProxy<Command<T>>>>,
+) -> i32
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<()>,
+{
+    let event_loop = match EventLoop::<Command<T>>::with_user_event() {
+        Ok(event_loop) => event_loop,
+        Err(e) => {
+            let _ = proxy_sender.send(Err(e));
+            return -1;
+        }
+    };
+    if let Err(e) = setup() {
+        let _ = proxy_sender.send(Err(e));
+        return -1;
+    }
+    let _ = proxy_sender.send(Ok(event_loop.create_proxy()));
+
+    event_loop.run(move |event, _window, _control_flow| {
+        let event = match event.map_nonuser_event() {
+            Ok(event) => event,
+            Err(Event::UserEvent(Command::User(event))) => Event::UserEvent(event),
+            Err(Event::UserEvent(Command::Run(task))) => return task(),
+            Err(Event::UserEvent(Command::Exit(exit_code))) => return post_quit_message(exit_code),
+            Err(_) => unreachable!("only user events are handed back"),
+        };
+        // Nobody is listening anymore
+        if events.send(event).is_err() {
+            post_quit_message(0);
+        }
+    })
+}
@@ -0,0 +1,132 @@
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll, Waker},
+    thread::{self, Thread},
+    time::Duration,
+};
+
+use futures_core::Stream;
+
+use super::{EventLoop, PumpStatus};
+use crate::{event::Event, window::WindowsWindow};
+
+/// How often a pending stream re-polls the message queue.
+const POLL_INTERVAL: Duration = Duration::from_millis(4);
+
+/// Flags shared with the thread re-polling a pending stream.
+#[derive(Default)]
+struct TickerState {
+    // Set while the stream is pending, the ticker sleeps otherwise.
+    pending: AtomicBool,
+    stopped: AtomicBool,
+}
+
+/// Waker of the task currently awaiting an [`EventStream`].
+#[derive(Default)]
+pub(crate) struct StreamWaker {
+    waker: Mutex<Option<Waker>>,
+}
+
+impl StreamWaker {
+    fn register(&self, waker: &Waker) {
+        *self.waker.lock().unwrap() = Some(waker.clone());
+    }
+
+    pub(crate) fn wake(&self) {
+        if let Some(waker) = self.waker.lock().unwrap().take() {
+            waker.wake();
+        }
+    }
+}
+
+/// The events of an [`EventLoop`] as an async [`Stream`].
+///
+/// The stream must be polled on the thread that owns the event loop. User
+/// events wake it right away; window messages are picked up on the next poll,
+/// which happens at least every few milliseconds while the stream is pending
+/// since Win32 can't notify another thread when a message is queued. Nothing
+/// is re-polled while the stream isn't awaited.
+///
+/// Ends once `WM_QUIT` is received.
+pub struct EventStream<'a, T: 'static> {
+    event_loop: &'a mut EventLoop<T>,
+    buffered: VecDeque<(Event<T>, WindowsWindow)>,
+    exited: bool,
+    ticker_state: Arc<TickerState>,
+    ticker: Thread,
+}
+
+impl<'a, T: 'static> EventStream<'a, T> {
+    pub(crate) fn new(event_loop: &'a mut EventLoop<T>) -> Self {
+        let ticker_state = Arc::new(TickerState::default());
+        let waker = event_loop.waker.clone();
+        let state = ticker_state.clone();
+        let ticker = thread::spawn(move || loop {
+            // Parked until poll_next returns Pending
+            while !state.pending.load(Ordering::Acquire) && !state.stopped.load(Ordering::Acquire) {
+                thread::park();
+            }
+            if state.stopped.load(Ordering::Acquire) {
+                break;
+            }
+            thread::sleep(POLL_INTERVAL);
+            state.pending.store(false, Ordering::Release);
+            waker.wake();
+        })
+        .thread()
+        .clone();
+
+        Self {
+            event_loop,
+            buffered: VecDeque::new(),
+            exited: false,
+            ticker_state,
+            ticker,
+        }
+    }
+}
+
+// Nothing in the stream is structurally pinned.
+impl<T: 'static> Unpin for EventStream<'_, T> {}
+
+impl<T: 'static> Stream for EventStream<'_, T> {
+    type Item = (Event<T>, WindowsWindow);
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.buffered.is_empty() && !this.exited {
+            // Registered before pumping so a wake-up in between isn't lost.
+            this.event_loop.waker.register(cx.waker());
+
+            let buffered = &mut this.buffered;
+            let status = this
+                .event_loop
+                .pump_events(Some(Duration::ZERO), |event, window, _| {
+                    buffered.push_back((event, *window))
+                });
+            this.exited = status != PumpStatus::Continue;
+        }
+
+        match this.buffered.pop_front() {
+            Some(item) => Poll::Ready(Some(item)),
+            None if this.exited => Poll::Ready(None),
+            None => {
+                this.ticker_state.pending.store(true, Ordering::Release);
+                this.ticker.unpark();
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<T: 'static> Drop for EventStream<'_, T> {
+    fn drop(&mut self) {
+        self.ticker_state.stopped.store(true, Ordering::Release);
+        self.ticker.unpark();
+    }
+}
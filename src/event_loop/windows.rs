@@ -0,0 +1,823 @@
+#[cfg(feature = "async")]
+use std::sync::Arc;
+use std::{
+    any::Any,
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    marker::PhantomData,
+    mem,
+    panic::{self, AssertUnwindSafe},
+    ptr::{self, NonNull},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        LazyLock,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Result};
+use windows::{
+    core::{w, PCWSTR},
+    Win32::{
+        Foundation::{
+            CloseHandle, GetLastError, ERROR_CLASS_ALREADY_EXISTS, HANDLE, HWND, LPARAM, LRESULT,
+            WAIT_ABANDONED_0, WAIT_OBJECT_0, WPARAM,
+        },
+        Graphics::Dwm::{DwmFlush, DwmGetCompositionTimingInfo, DWM_TIMING_INFO},
+        System::{
+            SystemInformation::GetTickCount,
+            SystemServices::MAXIMUM_WAIT_OBJECTS,
+            Threading::{
+                CreateWaitableTimerExW, SetWaitableTimer, CREATE_WAITABLE_TIMER_HIGH_RESOLUTION,
+                INFINITE, TIMER_ALL_ACCESS,
+            },
+        },
+        UI::WindowsAndMessaging::{
+            CreateWindowExW, DefWindowProcW, DestroyWindow, DispatchMessageW, GetMessageTime,
+            KillTimer, MsgWaitForMultipleObjectsEx, PeekMessageW, PostMessageW, RegisterClassW,
+            RegisterWindowMessageW, SetTimer, HMENU, HWND_MESSAGE, MSG, MWMO_INPUTAVAILABLE,
+            PM_NOREMOVE, PM_REMOVE, QS_ALLINPUT, WINDOW_EX_STYLE, WINDOW_STYLE, WM_HOTKEY, WM_QUIT,
+            WM_TIMER, WNDCLASSW,
+        },
+    },
+};
+
+#[cfg(feature = "async")]
+use super::stream::{EventStream, StreamWaker};
+use super::{ControlFlow, Metrics, PumpStatus};
+#[cfg(feature = "record")]
+use crate::record::Recorder;
+use crate::{
+    event::{Event, WindowEvent},
+    gamepad::Gamepads,
+    hotkey::HotkeyId,
+    window::windows::{get_instance_handle, translte_message, WindowsWindow},
+};
+
+/// Posted to the event loop's target window when user events are waiting.
+static USER_EVENT_MSG: LazyLock<u32> =
+    LazyLock::new(|| unsafe { RegisterWindowMessageW(w!("Paneless::UserEvent")) });
+
+const TARGET_WINDOW_CLASS: PCWSTR = w!("paneless_event_loop");
+
+/// Timer of the target window polling the gamepads.
+const GAMEPAD_TIMER_ID: usize = 1;
+
+#[cfg(feature = "record")]
+type RecordFn<T> = Box<dyn FnMut(&Event<T>)>;
+
+type EventHandler<'a> = dyn FnMut(Event, &WindowsWindow, &mut ControlFlow) + 'a;
+
+/// Per-thread state shared between the event loop and the window procedure.
+struct Runner {
+    // Taken while the handler runs, so a re-entrant window procedure call
+    // queues its event instead of aliasing the handler.
+    handler: Cell<Option<NonNull<EventHandler<'static>>>>,
+    running: Cell<bool>,
+    // Window that loop-level events are delivered with.
+    target: Cell<HWND>,
+    control_flow: Cell<ControlFlow>,
+    pending: RefCell<VecDeque<(HWND, Event, Option<Instant>)>>,
+    coalesce_cursor_moves: Cell<bool>,
+    // Latest cursor move not yet delivered, while coalescing.
+    cursor_move: RefCell<Option<(HWND, Event, Option<Instant>)>>,
+    // Message time of the event being delivered.
+    event_time: Cell<Option<Instant>>,
+    panic: RefCell<Option<Box<dyn Any + Send>>>,
+    metrics: RefCell<Metrics>,
+    gamepads: RefCell<Gamepads>,
+}
+
+thread_local! {
+    static RUNNER: Runner = const {
+        Runner {
+            handler: Cell::new(None),
+            running: Cell::new(false),
+            target: Cell::new(HWND(ptr::null_mut())),
+            control_flow: Cell::new(ControlFlow::Wait),
+            pending: RefCell::new(VecDeque::new()),
+            coalesce_cursor_moves: Cell::new(true),
+            cursor_move: RefCell::new(None),
+            event_time: Cell::new(None),
+            panic: RefCell::new(None),
+            metrics: RefCell::new(Metrics::new()),
+            gamepads: RefCell::new(Gamepads::new()),
+        }
+    };
+}
+
+impl Runner {
+    fn dispatch(&self, hwnd: HWND, event: Event) {
+        if !self.running.get() {
+            return;
+        }
+        // Only posted messages have a time of their own
+        let time = is_input_event(&event).then(message_time);
+
+        let is_cursor_move = matches!(
+            event,
+            Event::WindowEvent {
+                event: WindowEvent::CursorMoved { .. },
+                ..
+            }
+        );
+        if is_cursor_move && self.coalesce_cursor_moves.get() {
+            // Only a move of another window has to be delivered right away
+            let previous = self.cursor_move.replace(Some((hwnd, event, time)));
+            match previous {
+                Some((previous_hwnd, previous, previous_time)) if previous_hwnd != hwnd => {
+                    self.pending
+                        .borrow_mut()
+                        .push_back((previous_hwnd, previous, previous_time));
+                }
+                _ => return,
+            }
+        } else {
+            // Deliver the pending move first to keep events in order
+            if let Some(cursor_move) = self.cursor_move.take() {
+                self.pending.borrow_mut().push_back(cursor_move);
+            }
+            self.pending.borrow_mut().push_back((hwnd, event, time));
+        }
+        self.deliver_pending();
+    }
+
+    /// Runs once the message queue has been drained.
+    fn end_iteration(&self) {
+        // Also flushes the coalesced cursor move
+        self.dispatch(self.target.get(), Event::AboutToWait);
+    }
+
+    fn deliver_pending(&self) {
+        // Already inside the handler: the outer call drains the queue.
+        let Some(mut handler) = self.handler.take() else {
+            return;
+        };
+        loop {
+            // Once the handler panicked, nothing else is delivered until
+            // the panic has been re-raised outside the window procedure.
+            if self.panic.borrow().is_some() {
+                self.pending.borrow_mut().clear();
+                break;
+            }
+            let next = self.pending.borrow_mut().pop_front();
+            let Some((hwnd, event, time)) = next else {
+                break;
+            };
+            let window = WindowsWindow::from_hwnd(hwnd);
+            let mut control_flow = self.control_flow.get();
+            // Unwinding across the `extern "system"` window procedure is UB.
+            let started = Instant::now();
+            self.event_time.set(time);
+            let result = panic::catch_unwind(AssertUnwindSafe(|| unsafe {
+                handler.as_mut()(event, &window, &mut control_flow)
+            }));
+            self.event_time.set(None);
+            self.metrics.borrow_mut().record_callback(started.elapsed());
+            match result {
+                Ok(()) => self.control_flow.set(control_flow),
+                Err(payload) => *self.panic.borrow_mut() = Some(payload),
+            }
+        }
+        self.handler.set(Some(handler));
+    }
+
+    fn poll_gamepads(&self) {
+        let events = self.gamepads.borrow_mut().poll();
+        for (id, event) in events {
+            self.dispatch(self.target.get(), Event::Gamepad { id, event });
+        }
+    }
+
+    /// Runs user code other than the handler, e.g. a message hook, from
+    /// inside the window procedure. A panic is kept to be re-raised like one
+    /// of the handler, with `None` returned.
+    fn catch_panic<R>(&self, f: impl FnOnce() -> R) -> Option<R> {
+        match panic::catch_unwind(AssertUnwindSafe(f)) {
+            Ok(result) => Some(result),
+            Err(payload) => {
+                self.panic.borrow_mut().get_or_insert(payload);
+                None
+            }
+        }
+    }
+
+    /// Re-raises a panic caught in the handler, now that we're back from the
+    /// window procedure.
+    fn resume_panic(&self) {
+        let payload = self.panic.borrow_mut().take();
+        if let Some(payload) = payload {
+            panic::resume_unwind(payload);
+        }
+    }
+}
+
+/// When the input message behind the event being handled was posted, e.g. to
+/// measure input latency or to order input from several sources. `None`
+/// outside the event handler and for other events.
+///
+/// Covers keyboard, mouse, touch, pen and gesture window events, and
+/// [`Event::DeviceEvent`]. The message times have the resolution of the
+/// system tick, about 15 ms.
+pub fn event_time() -> Option<Instant> {
+    RUNNER.with(|runner| runner.event_time.get())
+}
+
+fn is_input_event(event: &Event) -> bool {
+    match event {
+        Event::WindowEvent { event, .. } => matches!(
+            event,
+            WindowEvent::KeyboardInput { .. }
+                | WindowEvent::ReceivedCharacter(_)
+                | WindowEvent::MouseInput { .. }
+                | WindowEvent::DoubleClick { .. }
+                | WindowEvent::CursorEntered
+                | WindowEvent::CursorLeft
+                | WindowEvent::CursorMoved { .. }
+                | WindowEvent::MouseWheel(_)
+                | WindowEvent::Touch(_)
+                | WindowEvent::Pen(_)
+                | WindowEvent::Gesture(_)
+        ),
+        Event::DeviceEvent { .. } => true,
+        _ => false,
+    }
+}
+
+/// Time of the message being handled.
+fn message_time() -> Instant {
+    let time = unsafe { GetMessageTime() } as u32;
+    tick_to_instant(time, unsafe { GetTickCount() }, Instant::now())
+}
+
+/// Converts a time of the millisecond tick counter, which wraps around every
+/// 49.7 days, given the current tick and instant.
+fn tick_to_instant(time: u32, now_tick: u32, now: Instant) -> Instant {
+    let age = Duration::from_millis(now_tick.wrapping_sub(time) as u64);
+    now.checked_sub(age).unwrap_or(now)
+}
+
+/// Forwards an event from the window procedure to the running event loop, if any.
+pub(crate) fn dispatch_event(hwnd: HWND, event: Event) {
+    RUNNER.with(|runner| runner.dispatch(hwnd, event));
+}
+
+/// Runs `f` from the window procedure or a hook procedure, where unwinding
+/// is UB. A panic resumes from the event loop instead, `None` is returned.
+pub(crate) fn catch_panic<R>(f: impl FnOnce() -> R) -> Option<R> {
+    RUNNER.with(|runner| runner.catch_panic(f))
+}
+
+/// Called periodically while a window is in a modal move/resize loop, which
+/// pumps messages itself and keeps our loop from ever reaching the end of an
+/// iteration.
+pub(crate) fn modal_loop_tick() {
+    RUNNER.with(|runner| runner.end_iteration());
+}
+
+/// Owns the message loop of the thread that created it.
+///
+/// Events that don't belong to one of the application's windows, like
+/// [`Event::UserEvent`], are delivered with the event loop's own hidden
+/// message-only window.
+pub struct EventLoop<T: 'static = ()> {
+    target: WindowsWindow,
+    sender: Sender<T>,
+    receiver: Receiver<T>,
+    handles: Vec<HANDLE>,
+    // High resolution timer backing WaitUntil, if the OS supports one.
+    wait_timer: Option<HANDLE>,
+    frame_events: bool,
+    coalesce_cursor_moves: bool,
+    metrics_since: Instant,
+    #[cfg(feature = "record")]
+    recorder: RefCell<Option<RecordFn<T>>>,
+    #[cfg(feature = "async")]
+    pub(crate) waker: Arc<StreamWaker>,
+}
+
+impl EventLoop<()> {
+    pub fn new() -> Result<Self> {
+        Self::with_user_event()
+    }
+}
+
+impl<T: 'static> EventLoop<T> {
+    /// Creates an event loop whose proxies send user events of type `T`.
+    pub fn with_user_event() -> Result<Self> {
+        let target = WindowsWindow::from_hwnd(create_target_window()?);
+        let (sender, receiver) = mpsc::channel();
+        Ok(Self {
+            target,
+            sender,
+            receiver,
+            handles: Vec::new(),
+            wait_timer: create_high_resolution_timer(),
+            frame_events: false,
+            coalesce_cursor_moves: true,
+            metrics_since: Instant::now(),
+            #[cfg(feature = "record")]
+            recorder: RefCell::new(None),
+            #[cfg(feature = "async")]
+            waker: Arc::default(),
+        })
+    }
+
+    pub(crate) fn target_hwnd(&self) -> HWND {
+        self.target.hwnd()
+    }
+
+    /// Creates a handle that can wake this event loop from any thread.
+    pub fn create_proxy(&self) -> EventLoopProxy<T> {
+        EventLoopProxy {
+            sender: self.sender.clone(),
+            target: self.target.hwnd().0 as isize,
+            #[cfg(feature = "async")]
+            waker: self.waker.clone(),
+        }
+    }
+
+    /// Delivers the events of this loop as an async stream instead of through
+    /// a callback. See [`EventStream`].
+    #[cfg(feature = "async")]
+    pub fn event_stream(&mut self) -> EventStream<'_, T> {
+        EventStream::new(self)
+    }
+
+    /// Runs the message loop until `WM_QUIT` is received and returns its exit
+    /// code. See [`EventLoop::run_return`].
+    pub fn run<F>(mut self, event_handler: F) -> i32
+    where
+        F: FnMut(Event<T>, &WindowsWindow, &mut ControlFlow),
+    {
+        self.run_return(event_handler)
+    }
+
+    /// Runs the message loop, calling `event_handler` for every event the
+    /// windows of this thread receive, until `WM_QUIT` is received. Returns
+    /// the exit code posted with it, and can be called again afterwards.
+    ///
+    /// If `event_handler` panics, the loop stops delivering events and the
+    /// panic resumes from this call once the window procedure has returned.
+    ///
+    /// Between iterations the loop waits according to the [`ControlFlow`]
+    /// the handler left behind, [`ControlFlow::Wait`] by default.
+    pub fn run_return<F>(&mut self, event_handler: F) -> i32
+    where
+        F: FnMut(Event<T>, &WindowsWindow, &mut ControlFlow),
+    {
+        self.with_handler(event_handler, || {
+            RUNNER.with(|runner| runner.control_flow.set(ControlFlow::Wait));
+            loop {
+                if let Some(exit_code) = drain_messages() {
+                    return exit_code;
+                }
+                if self.frame_events && self.wait_for_frame() {
+                    // Still pick up signaled handles without blocking
+                    self.wait(Some(Duration::ZERO));
+                    continue;
+                }
+
+                match RUNNER.with(|runner| runner.control_flow.get()) {
+                    ControlFlow::Poll => self.wait(Some(Duration::ZERO)),
+                    ControlFlow::Wait => self.wait(None),
+                    ControlFlow::WaitUntil(deadline) => {
+                        self.wait(Some(deadline.saturating_duration_since(Instant::now())))
+                    }
+                }
+            }
+        })
+    }
+
+    /// Dispatches the messages already queued, waiting up to `timeout` for
+    /// one to arrive if there are none, then returns without blocking any
+    /// further. `None` waits indefinitely.
+    ///
+    /// Meant for applications that own their main loop and call this once
+    /// per frame instead of handing control over to [`EventLoop::run`].
+    pub fn pump_events<F>(&mut self, timeout: Option<Duration>, event_handler: F) -> PumpStatus
+    where
+        F: FnMut(Event<T>, &WindowsWindow, &mut ControlFlow),
+    {
+        self.with_handler(event_handler, || {
+            if has_pending_messages() {
+                self.wait(Some(Duration::ZERO));
+            } else {
+                self.wait(timeout);
+            }
+            match drain_messages() {
+                Some(exit_code) => PumpStatus::Exit(exit_code),
+                None => PumpStatus::Continue,
+            }
+        })
+    }
+
+    /// Merges the cursor moves of a window queued during one loop iteration
+    /// into a single [`WindowEvent::CursorMoved`] with the latest position.
+    /// Enabled by default, disable it to receive every sample.
+    pub fn set_coalesce_cursor_moves(&mut self, coalesce: bool) {
+        self.coalesce_cursor_moves = coalesce;
+    }
+
+    /// Writes every event delivered to the handler to `recorder`, until
+    /// replaced or cleared with `None`.
+    #[cfg(feature = "record")]
+    pub fn set_recorder(&mut self, recorder: Option<Recorder>)
+    where
+        T: serde::Serialize,
+    {
+        let recorder = recorder.map(|mut recorder| {
+            Box::new(move |event: &Event<T>| {
+                if let Err(e) = recorder.record(event) {
+                    crate::utils::trace::log_error!("Error while recording an event: {:?}", e);
+                }
+            }) as RecordFn<T>
+        });
+        *self.recorder.borrow_mut() = recorder;
+    }
+
+    /// Counters about the messages and events handled on this thread since
+    /// the loop was created or [`EventLoop::reset_metrics`] was called.
+    pub fn metrics(&self) -> Metrics {
+        let mut metrics = RUNNER.with(|runner| runner.metrics.borrow().clone());
+        metrics.elapsed = self.metrics_since.elapsed();
+        metrics
+    }
+
+    pub fn reset_metrics(&mut self) {
+        RUNNER.with(|runner| *runner.metrics.borrow_mut() = Metrics::new());
+        self.metrics_since = Instant::now();
+    }
+
+    /// Paces [`EventLoop::run`] on the compositor: after draining the queue
+    /// the loop blocks until the next DWM composition and delivers
+    /// [`Event::Frame`], so rendering from it neither tears nor runs
+    /// uncapped. The [`ControlFlow`] is ignored while enabled, unless
+    /// composition isn't available.
+    pub fn set_frame_events(&mut self, enabled: bool) {
+        self.frame_events = enabled;
+    }
+
+    /// Polls the XInput controllers every `interval` and delivers what
+    /// changed as [`Event::Gamepad`], `None` stops. Polling happens through
+    /// a timer on the loop's thread, so even [`ControlFlow::Wait`] wakes up
+    /// for it.
+    pub fn set_gamepad_polling(&mut self, interval: Option<Duration>) -> Result<()> {
+        let hwnd = self.target.hwnd();
+        match interval {
+            Some(interval) => {
+                let millis = interval.as_millis().clamp(1, u32::MAX as u128) as u32;
+                if unsafe { SetTimer(hwnd, GAMEPAD_TIMER_ID, millis, None) } == 0 {
+                    bail!("Could not start polling gamepads: {:?}", unsafe {
+                        GetLastError()
+                    });
+                }
+            }
+            None => unsafe {
+                let _ = KillTimer(hwnd, GAMEPAD_TIMER_ID);
+            },
+        }
+        Ok(())
+    }
+
+    /// Blocks until the compositor's next frame and delivers it. Returns
+    /// `false` if DWM composition isn't available.
+    fn wait_for_frame(&self) -> bool {
+        if unsafe { DwmFlush() }.is_err() {
+            return false;
+        }
+
+        let mut timing_info = DWM_TIMING_INFO {
+            cbSize: mem::size_of::<DWM_TIMING_INFO>() as u32,
+            ..Default::default()
+        };
+        // Since Windows 8.1 only the desktop-wide timing can be queried
+        let refresh_period =
+            unsafe { DwmGetCompositionTimingInfo(HWND::default(), &mut timing_info) }
+                .ok()
+                .map(|()| timing_info.rateRefresh)
+                .filter(|rate| rate.uiNumerator != 0)
+                .map(|rate| {
+                    Duration::from_secs_f64(rate.uiDenominator as f64 / rate.uiNumerator as f64)
+                });
+        dispatch_event(self.target.hwnd(), Event::Frame { refresh_period });
+        true
+    }
+
+    /// Makes the loop wake up when `handle` is signaled (an event, semaphore,
+    /// process, pipe, ...) and deliver [`Event::HandleSignaled`] with the
+    /// index returned here. The handle must stay valid while the loop runs.
+    ///
+    /// Auto-reset objects are reset by the wait, manual-reset ones keep
+    /// firing every iteration until reset.
+    pub fn register_handle(&mut self, handle: HANDLE) -> Result<usize> {
+        // One wait slot is taken by the message queue
+        if self.handles.len() + 1 >= MAXIMUM_WAIT_OBJECTS as usize {
+            bail!(
+                "Can not wait on more than {} handles",
+                MAXIMUM_WAIT_OBJECTS - 1
+            );
+        }
+        self.handles.push(handle);
+        Ok(self.handles.len() - 1)
+    }
+
+    /// Waits for messages or registered handles, delivering the signaled
+    /// handle if any.
+    fn wait(&self, timeout: Option<Duration>) {
+        let signaled = match (self.wait_timer, timeout) {
+            // Wait timeouts follow the ~15 ms system tick, the waitable timer
+            // wakes us within a millisecond of the deadline.
+            (Some(timer), Some(timeout)) if !timeout.is_zero() => {
+                // Negative due times are relative, in 100 ns units
+                let due_time = -((timeout.as_nanos() / 100).min(i64::MAX as u128) as i64);
+                match unsafe { SetWaitableTimer(timer, &due_time, 0, None, None, false) } {
+                    Ok(()) => {
+                        let mut handles = self.handles.clone();
+                        handles.push(timer);
+                        wait_for_messages(&handles, None)
+                            .filter(|index| *index < self.handles.len())
+                    }
+                    Err(_) => wait_for_messages(&self.handles, Some(timeout)),
+                }
+            }
+            _ => wait_for_messages(&self.handles, timeout),
+        };
+        if let Some(index) = signaled {
+            dispatch_event(self.target.hwnd(), Event::HandleSignaled(index));
+        }
+    }
+
+    /// Installs `event_handler` on this thread's runner while `body` pumps
+    /// messages.
+    fn with_handler<F, R>(&self, mut event_handler: F, body: impl FnOnce() -> R) -> R
+    where
+        F: FnMut(Event<T>, &WindowsWindow, &mut ControlFlow),
+    {
+        let receiver = &self.receiver;
+        let mut deliver =
+            |event: Event<T>, window: &WindowsWindow, control_flow: &mut ControlFlow| {
+                #[cfg(feature = "record")]
+                if let Some(record) = self.recorder.borrow_mut().as_mut() {
+                    record(&event);
+                }
+                event_handler(event, window, control_flow);
+            };
+        let mut handler =
+            move |event: Event, window: &WindowsWindow, control_flow: &mut ControlFlow| match event
+                .map_nonuser_event()
+            {
+                Ok(event) => deliver(event, window, control_flow),
+                Err(_) => {
+                    for user_event in receiver.try_iter() {
+                        deliver(Event::UserEvent(user_event), window, control_flow);
+                    }
+                }
+            };
+        let _guard = HandlerGuard::install(&mut handler);
+        RUNNER.with(|runner| {
+            runner.target.set(self.target.hwnd());
+            runner.coalesce_cursor_moves.set(self.coalesce_cursor_moves);
+        });
+        body()
+    }
+}
+
+/// Dispatches every queued message, returning the exit code if `WM_QUIT` was
+/// among them.
+fn drain_messages() -> Option<i32> {
+    while let Some(msg) = peek_next_message() {
+        if msg.message == WM_QUIT {
+            return Some(msg.wParam.0 as i32);
+        }
+        RUNNER.with(|runner| {
+            let mut metrics = runner.metrics.borrow_mut();
+            metrics.messages += 1;
+            // Message times come from the same millisecond tick counter
+            let latency = unsafe { GetTickCount() }.wrapping_sub(msg.time);
+            metrics
+                .dispatch_latency
+                .record(Duration::from_millis(latency as u64));
+        });
+        let _ = translte_message(&msg);
+        unsafe {
+            DispatchMessageW(&msg);
+        }
+        RUNNER.with(|runner| runner.resume_panic());
+    }
+    RUNNER.with(|runner| {
+        runner.end_iteration();
+        runner.resume_panic();
+    });
+    None
+}
+
+/// Installs an event handler on the thread's runner for as long as it lives.
+struct HandlerGuard<'a> {
+    _handler: PhantomData<&'a mut ()>,
+}
+
+impl<'a> HandlerGuard<'a> {
+    fn install(handler: &'a mut EventHandler<'_>) -> Self {
+        // Erasing the lifetime is fine: the guard borrows the handler and
+        // uninstalls it on drop, before the borrow ends.
+        let handler: NonNull<EventHandler<'static>> =
+            unsafe { std::mem::transmute(NonNull::from(handler)) };
+        RUNNER.with(|runner| {
+            assert!(!runner.running.get(), "The event loop is already running");
+            runner.handler.set(Some(handler));
+            runner.running.set(true);
+        });
+        Self {
+            _handler: PhantomData,
+        }
+    }
+}
+
+impl Drop for HandlerGuard<'_> {
+    fn drop(&mut self) {
+        RUNNER.with(|runner| {
+            runner.running.set(false);
+            runner.handler.set(None);
+            runner.pending.borrow_mut().clear();
+            runner.cursor_move.take();
+            runner.panic.take();
+        });
+    }
+}
+
+impl<T> Drop for EventLoop<T> {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = DestroyWindow(self.target.hwnd());
+            if let Some(timer) = self.wait_timer {
+                let _ = CloseHandle(timer);
+            }
+        }
+    }
+}
+
+/// Sends user events to an [`EventLoop`] from any thread.
+pub struct EventLoopProxy<T: 'static> {
+    sender: Sender<T>,
+    // HWNDs aren't Send, but posting messages to one from another thread is fine.
+    target: isize,
+    #[cfg(feature = "async")]
+    waker: Arc<StreamWaker>,
+}
+
+impl<T: 'static> EventLoopProxy<T> {
+    /// Queues `event` and wakes the event loop, which delivers it as an
+    /// [`Event::UserEvent`]. Fails once the event loop has been dropped.
+    pub fn send_event(&self, event: T) -> Result<()> {
+        if self.sender.send(event).is_err() {
+            bail!("The event loop has been closed");
+        }
+        let target = HWND(self.target as *mut _);
+        if let Err(e) = unsafe { PostMessageW(target, *USER_EVENT_MSG, WPARAM(0), LPARAM(0)) } {
+            bail!("Could not wake the event loop: {:?}", e);
+        }
+        #[cfg(feature = "async")]
+        self.waker.wake();
+        Ok(())
+    }
+}
+
+impl<T: 'static> Clone for EventLoopProxy<T> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            target: self.target,
+            #[cfg(feature = "async")]
+            waker: self.waker.clone(),
+        }
+    }
+}
+
+/// High resolution waitable timers need Windows 10 1803 or newer.
+fn create_high_resolution_timer() -> Option<HANDLE> {
+    unsafe {
+        CreateWaitableTimerExW(
+            None,
+            PCWSTR::null(),
+            CREATE_WAITABLE_TIMER_HIGH_RESOLUTION,
+            TIMER_ALL_ACCESS.0,
+        )
+    }
+    .ok()
+}
+
+fn create_target_window() -> Result<HWND> {
+    let h_instance = get_instance_handle();
+
+    let wc = WNDCLASSW {
+        lpfnWndProc: Some(target_window_procedure),
+        hInstance: h_instance.into(),
+        lpszClassName: TARGET_WINDOW_CLASS,
+        ..Default::default()
+    };
+    // Every event loop shares the class, so it may already be registered.
+    if unsafe { RegisterClassW(&wc) } == 0 {
+        let last_error = unsafe { GetLastError() };
+        if last_error != ERROR_CLASS_ALREADY_EXISTS {
+            bail!(
+                "Could not register the event loop window class, error code: {:?}",
+                last_error
+            );
+        }
+    }
+
+    let hwnd = unsafe {
+        CreateWindowExW(
+            WINDOW_EX_STYLE::default(),
+            TARGET_WINDOW_CLASS,
+            PCWSTR::null(),
+            WINDOW_STYLE::default(),
+            0,
+            0,
+            0,
+            0,
+            HWND_MESSAGE,
+            HMENU::default(),
+            h_instance,
+            None,
+        )?
+    };
+    Ok(hwnd)
+}
+
+unsafe extern "system" fn target_window_procedure(
+    hwnd: HWND,
+    msg: u32,
+    w_param: WPARAM,
+    l_param: LPARAM,
+) -> LRESULT {
+    #[cfg(feature = "tracing")]
+    let _span = crate::utils::trace::message_span(hwnd, msg);
+    if msg == *USER_EVENT_MSG {
+        dispatch_event(hwnd, Event::UserEvent(()));
+        return LRESULT(0);
+    }
+    if msg == WM_HOTKEY {
+        dispatch_event(hwnd, Event::GlobalHotkey(HotkeyId(w_param.0 as i32)));
+        return LRESULT(0);
+    }
+    if msg == WM_TIMER && w_param.0 == GAMEPAD_TIMER_ID {
+        RUNNER.with(|runner| runner.poll_gamepads());
+        return LRESULT(0);
+    }
+    DefWindowProcW(hwnd, msg, w_param, l_param)
+}
+
+pub fn peek_next_message() -> Option<MSG> {
+    let mut msg = MSG::default();
+    let available = unsafe { PeekMessageW(&mut msg, HWND::default(), 0, 0, PM_REMOVE) };
+    available.as_bool().then_some(msg)
+}
+
+pub fn has_pending_messages() -> bool {
+    let mut msg = MSG::default();
+    unsafe { PeekMessageW(&mut msg, HWND::default(), 0, 0, PM_NOREMOVE) }.as_bool()
+}
+
+/// Blocks until a message is available, one of `handles` is signaled or
+/// `timeout` elapses. Returns the index of the signaled handle.
+pub fn wait_for_messages(handles: &[HANDLE], timeout: Option<Duration>) -> Option<usize> {
+    let millis = match timeout {
+        // Round up so a short timeout doesn't turn into a busy loop.
+        Some(timeout) => timeout
+            .as_nanos()
+            .div_ceil(1_000_000)
+            .min((INFINITE - 1) as u128) as u32,
+        None => INFINITE,
+    };
+    let handles = (!handles.is_empty()).then_some(handles);
+    let count = handles.map_or(0, |handles| handles.len() as u32);
+    let result =
+        unsafe { MsgWaitForMultipleObjectsEx(handles, millis, QS_ALLINPUT, MWMO_INPUTAVAILABLE) };
+
+    // Abandoned mutexes count as signaled, the owner is gone either way
+    [WAIT_OBJECT_0, WAIT_ABANDONED_0]
+        .into_iter()
+        .find_map(|base| result.0.checked_sub(base.0).filter(|index| *index < count))
+        .map(|index| index as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_message_times() {
+        let now = Instant::now() + Duration::from_secs(10);
+        assert_eq!(
+            tick_to_instant(1000, 1250, now),
+            now - Duration::from_millis(250)
+        );
+        // The tick counter wrapped around since
+        assert_eq!(
+            tick_to_instant(u32::MAX - 9, 10, now),
+            now - Duration::from_millis(20)
+        );
+    }
+}
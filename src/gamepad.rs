@@ -0,0 +1,277 @@
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Result};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use windows::Win32::{
+    Foundation::{ERROR_DEVICE_NOT_CONNECTED, ERROR_SUCCESS},
+    UI::Input::XboxController::*,
+};
+
+use crate::event::{ElementState, GamepadEvent};
+
+/// XInput handles up to four controllers.
+const MAX_GAMEPADS: usize = 4;
+
+/// Probing an empty slot is slow, so they are only checked this often.
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// An XInput controller, by the player slot it occupies from 0 to 3.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GamepadId(pub u32);
+
+impl GamepadId {
+    /// Runs the low and high frequency motors at a speed from 0 to 1, until
+    /// set again.
+    pub fn set_rumble(self, low_frequency: f32, high_frequency: f32) -> Result<()> {
+        let speed = |speed: f32| (speed.clamp(0.0, 1.0) * u16::MAX as f32) as u16;
+        let vibration = XINPUT_VIBRATION {
+            wLeftMotorSpeed: speed(low_frequency),
+            wRightMotorSpeed: speed(high_frequency),
+        };
+        match unsafe { XInputSetState(self.0, &vibration) } {
+            code if code == ERROR_SUCCESS.0 => Ok(()),
+            code if code == ERROR_DEVICE_NOT_CONNECTED.0 => {
+                bail!("Gamepad {} is not connected", self.0)
+            }
+            code => bail!(
+                "Could not set the rumble of gamepad {}, error code: {}",
+                self.0,
+                code
+            ),
+        }
+    }
+}
+
+/// Buttons named after their position, as on an Xbox controller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum GamepadButton {
+    /// A
+    South,
+    /// B
+    East,
+    /// X
+    West,
+    /// Y
+    North,
+    LeftShoulder,
+    RightShoulder,
+    /// Pressing the left stick.
+    LeftThumb,
+    RightThumb,
+    Start,
+    Back,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+const BUTTONS: [(XINPUT_GAMEPAD_BUTTON_FLAGS, GamepadButton); 14] = [
+    (XINPUT_GAMEPAD_A, GamepadButton::South),
+    (XINPUT_GAMEPAD_B, GamepadButton::East),
+    (XINPUT_GAMEPAD_X, GamepadButton::West),
+    (XINPUT_GAMEPAD_Y, GamepadButton::North),
+    (XINPUT_GAMEPAD_LEFT_SHOULDER, GamepadButton::LeftShoulder),
+    (XINPUT_GAMEPAD_RIGHT_SHOULDER, GamepadButton::RightShoulder),
+    (XINPUT_GAMEPAD_LEFT_THUMB, GamepadButton::LeftThumb),
+    (XINPUT_GAMEPAD_RIGHT_THUMB, GamepadButton::RightThumb),
+    (XINPUT_GAMEPAD_START, GamepadButton::Start),
+    (XINPUT_GAMEPAD_BACK, GamepadButton::Back),
+    (XINPUT_GAMEPAD_DPAD_UP, GamepadButton::DPadUp),
+    (XINPUT_GAMEPAD_DPAD_DOWN, GamepadButton::DPadDown),
+    (XINPUT_GAMEPAD_DPAD_LEFT, GamepadButton::DPadLeft),
+    (XINPUT_GAMEPAD_DPAD_RIGHT, GamepadButton::DPadRight),
+];
+
+/// Sticks go from -1 to 1, with up and right positive. Triggers go from 0
+/// to 1. Both read 0 inside their dead zone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+const AXES: [GamepadAxis; 6] = [
+    GamepadAxis::LeftStickX,
+    GamepadAxis::LeftStickY,
+    GamepadAxis::RightStickX,
+    GamepadAxis::RightStickY,
+    GamepadAxis::LeftTrigger,
+    GamepadAxis::RightTrigger,
+];
+
+/// The state of a controller, with the dead zones applied.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+struct GamepadState {
+    buttons: u16,
+    // In the order of AXES
+    axes: [f32; 6],
+}
+
+impl From<&XINPUT_GAMEPAD> for GamepadState {
+    fn from(gamepad: &XINPUT_GAMEPAD) -> Self {
+        let (left_x, left_y) = stick(
+            gamepad.sThumbLX,
+            gamepad.sThumbLY,
+            XINPUT_GAMEPAD_LEFT_THUMB_DEADZONE.0,
+        );
+        let (right_x, right_y) = stick(
+            gamepad.sThumbRX,
+            gamepad.sThumbRY,
+            XINPUT_GAMEPAD_RIGHT_THUMB_DEADZONE.0,
+        );
+        Self {
+            buttons: gamepad.wButtons.0,
+            axes: [
+                left_x,
+                left_y,
+                right_x,
+                right_y,
+                trigger(gamepad.bLeftTrigger),
+                trigger(gamepad.bRightTrigger),
+            ],
+        }
+    }
+}
+
+/// Applies a radial dead zone, so the stick doesn't snap to the axes, and
+/// rescales what's left to the full range.
+fn stick(x: i16, y: i16, dead_zone: u16) -> (f32, f32) {
+    let (x, y) = (x as f32, y as f32);
+    let magnitude = x.hypot(y);
+    let dead_zone = dead_zone as f32;
+    if magnitude <= dead_zone {
+        return (0.0, 0.0);
+    }
+    let max = i16::MAX as f32;
+    let scale = (magnitude.min(max) - dead_zone) / (max - dead_zone) / magnitude;
+    ((x * scale).clamp(-1.0, 1.0), (y * scale).clamp(-1.0, 1.0))
+}
+
+fn trigger(value: u8) -> f32 {
+    let threshold = XINPUT_GAMEPAD_TRIGGER_THRESHOLD.0 as u8;
+    value.saturating_sub(threshold) as f32 / (u8::MAX - threshold) as f32
+}
+
+/// Events for the difference between two states of a controller.
+fn changes(old: &GamepadState, new: &GamepadState) -> Vec<GamepadEvent> {
+    let buttons = BUTTONS.iter().filter_map(|(flag, button)| {
+        let (was, is) = (old.buttons & flag.0 != 0, new.buttons & flag.0 != 0);
+        (was != is).then_some(GamepadEvent::Button {
+            button: *button,
+            state: if is {
+                ElementState::Pressed
+            } else {
+                ElementState::Released
+            },
+        })
+    });
+    let axes = AXES
+        .iter()
+        .zip(old.axes.iter().zip(new.axes))
+        .filter(|(_, (old, new))| **old != *new)
+        .map(|(axis, (_, value))| GamepadEvent::Axis { axis: *axis, value });
+    buttons.chain(axes).collect()
+}
+
+/// Last state seen of every slot, polled by the event loop.
+pub(crate) struct Gamepads {
+    slots: [Option<GamepadState>; MAX_GAMEPADS],
+    // When the empty slots are probed next.
+    next_probe: Option<Instant>,
+}
+
+impl Gamepads {
+    pub(crate) const fn new() -> Self {
+        Self {
+            slots: [None; MAX_GAMEPADS],
+            next_probe: None,
+        }
+    }
+
+    /// Reads every controller and reports what changed since the last poll.
+    pub(crate) fn poll(&mut self) -> Vec<(GamepadId, GamepadEvent)> {
+        let now = Instant::now();
+        let probe = self.next_probe.is_none_or(|next_probe| now >= next_probe);
+        if probe {
+            self.next_probe = Some(now + RECONNECT_INTERVAL);
+        }
+
+        let mut events = Vec::new();
+        for (index, slot) in self.slots.iter_mut().enumerate() {
+            if slot.is_none() && !probe {
+                continue;
+            }
+            let id = GamepadId(index as u32);
+            let mut state = XINPUT_STATE::default();
+            if unsafe { XInputGetState(id.0, &mut state) } != ERROR_SUCCESS.0 {
+                if slot.take().is_some() {
+                    events.push((id, GamepadEvent::Disconnected));
+                }
+                continue;
+            }
+            let new = GamepadState::from(&state.Gamepad);
+            let old = slot.replace(new).unwrap_or_else(|| {
+                events.push((id, GamepadEvent::Connected));
+                GamepadState::default()
+            });
+            events.extend(changes(&old, &new).into_iter().map(|event| (id, event)));
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applies_dead_zones() {
+        assert_eq!(stick(5000, -5000, 7849), (0.0, 0.0));
+        assert_eq!(stick(i16::MAX, 0, 7849), (1.0, 0.0));
+        let (x, y) = stick(0, -20000, 7849);
+        assert_eq!(x, 0.0);
+        assert!((y + (20000.0 - 7849.0) / (32767.0 - 7849.0)).abs() < 1e-6);
+
+        assert_eq!(trigger(20), 0.0);
+        assert_eq!(trigger(u8::MAX), 1.0);
+    }
+
+    #[test]
+    fn reports_changes() {
+        let old = GamepadState {
+            buttons: XINPUT_GAMEPAD_A.0,
+            axes: [0.0; 6],
+        };
+        let mut new = GamepadState {
+            buttons: XINPUT_GAMEPAD_B.0,
+            ..old
+        };
+        new.axes[4] = 0.5;
+        assert_eq!(
+            changes(&old, &new),
+            [
+                GamepadEvent::Button {
+                    button: GamepadButton::South,
+                    state: ElementState::Released
+                },
+                GamepadEvent::Button {
+                    button: GamepadButton::East,
+                    state: ElementState::Pressed
+                },
+                GamepadEvent::Axis {
+                    axis: GamepadAxis::LeftTrigger,
+                    value: 0.5
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,493 @@
+use std::{
+    ffi::{c_void, CString},
+    mem, ptr,
+};
+
+use anyhow::{bail, Result};
+use windows::{
+    core::{s, w, PCSTR},
+    Win32::{
+        Foundation::{BOOL, HWND},
+        Graphics::{
+            Gdi::{GetDC, ReleaseDC, HDC},
+            OpenGL::{
+                wglCreateContext, wglDeleteContext, wglGetCurrentContext, wglGetCurrentDC,
+                wglGetProcAddress, wglMakeCurrent, ChoosePixelFormat, DescribePixelFormat,
+                SetPixelFormat, SwapBuffers, HGLRC, PFD_DOUBLEBUFFER, PFD_DRAW_TO_WINDOW,
+                PFD_MAIN_PLANE, PFD_SUPPORT_OPENGL, PFD_TYPE_RGBA, PIXELFORMATDESCRIPTOR,
+            },
+        },
+        System::LibraryLoader::{GetModuleHandleW, GetProcAddress},
+        UI::WindowsAndMessaging::{
+            CreateWindowExW, DestroyWindow, GetClassLongPtrW, CS_OWNDC, GCL_STYLE, HMENU,
+            WINDOW_EX_STYLE, WS_OVERLAPPEDWINDOW,
+        },
+    },
+};
+
+use crate::window::{
+    windows::{get_instance_handle, window_state},
+    WindowsWindow,
+};
+
+// WGL_ARB_pixel_format
+const WGL_DRAW_TO_WINDOW_ARB: i32 = 0x2001;
+const WGL_ACCELERATION_ARB: i32 = 0x2003;
+const WGL_SUPPORT_OPENGL_ARB: i32 = 0x2010;
+const WGL_DOUBLE_BUFFER_ARB: i32 = 0x2011;
+const WGL_PIXEL_TYPE_ARB: i32 = 0x2013;
+const WGL_COLOR_BITS_ARB: i32 = 0x2014;
+const WGL_ALPHA_BITS_ARB: i32 = 0x201b;
+const WGL_DEPTH_BITS_ARB: i32 = 0x2022;
+const WGL_STENCIL_BITS_ARB: i32 = 0x2023;
+const WGL_FULL_ACCELERATION_ARB: i32 = 0x2027;
+const WGL_TYPE_RGBA_ARB: i32 = 0x202b;
+// WGL_ARB_multisample
+const WGL_SAMPLE_BUFFERS_ARB: i32 = 0x2041;
+const WGL_SAMPLES_ARB: i32 = 0x2042;
+// WGL_ARB_framebuffer_sRGB
+const WGL_FRAMEBUFFER_SRGB_CAPABLE_ARB: i32 = 0x20a9;
+// WGL_ARB_create_context and WGL_ARB_create_context_profile
+const WGL_CONTEXT_MAJOR_VERSION_ARB: i32 = 0x2091;
+const WGL_CONTEXT_MINOR_VERSION_ARB: i32 = 0x2092;
+const WGL_CONTEXT_FLAGS_ARB: i32 = 0x2094;
+const WGL_CONTEXT_PROFILE_MASK_ARB: i32 = 0x9126;
+const WGL_CONTEXT_DEBUG_BIT_ARB: i32 = 0x1;
+const WGL_CONTEXT_CORE_PROFILE_BIT_ARB: i32 = 0x1;
+const WGL_CONTEXT_COMPATIBILITY_PROFILE_BIT_ARB: i32 = 0x2;
+
+type ChoosePixelFormatArbFn =
+    unsafe extern "system" fn(HDC, *const i32, *const f32, u32, *mut i32, *mut u32) -> BOOL;
+type CreateContextAttribsArbFn = unsafe extern "system" fn(HDC, HGLRC, *const i32) -> HGLRC;
+type SwapIntervalExtFn = unsafe extern "system" fn(i32) -> BOOL;
+
+/// What [`GlContext::new`] asks the driver for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GlConfig {
+    version: (u8, u8),
+    core_profile: bool,
+    srgb: bool,
+    samples: u8,
+    depth_bits: u8,
+    stencil_bits: u8,
+    debug: bool,
+}
+
+impl Default for GlConfig {
+    fn default() -> Self {
+        Self {
+            version: (3, 3),
+            core_profile: true,
+            srgb: true,
+            samples: 0,
+            depth_bits: 24,
+            stencil_bits: 8,
+            debug: false,
+        }
+    }
+}
+
+impl GlConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// OpenGL version, 3.3 by default.
+    pub fn with_version(mut self, major: u8, minor: u8) -> Self {
+        self.version = (major, minor);
+        self
+    }
+
+    /// Core profile (the default), or compatibility profile with the
+    /// deprecated functions.
+    pub fn with_core_profile(mut self, core_profile: bool) -> Self {
+        self.core_profile = core_profile;
+        self
+    }
+
+    /// An sRGB capable framebuffer, on by default. Still needs
+    /// `GL_FRAMEBUFFER_SRGB` enabled to convert on writes.
+    pub fn with_srgb(mut self, srgb: bool) -> Self {
+        self.srgb = srgb;
+        self
+    }
+
+    /// Samples per pixel for multisampling, none by default.
+    pub fn with_samples(mut self, samples: u8) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    /// Bits of the depth and stencil buffers, 24 and 8 by default.
+    pub fn with_depth_stencil(mut self, depth_bits: u8, stencil_bits: u8) -> Self {
+        self.depth_bits = depth_bits;
+        self.stencil_bits = stencil_bits;
+        self
+    }
+
+    /// A debug context, for `glDebugMessageCallback`.
+    pub fn with_debug(mut self, debug: bool) -> Self {
+        self.debug = debug;
+        self
+    }
+
+    fn pixel_format_attributes(&self) -> Vec<i32> {
+        let mut attributes = vec![
+            WGL_DRAW_TO_WINDOW_ARB,
+            1,
+            WGL_SUPPORT_OPENGL_ARB,
+            1,
+            WGL_DOUBLE_BUFFER_ARB,
+            1,
+            WGL_ACCELERATION_ARB,
+            WGL_FULL_ACCELERATION_ARB,
+            WGL_PIXEL_TYPE_ARB,
+            WGL_TYPE_RGBA_ARB,
+            WGL_COLOR_BITS_ARB,
+            32,
+            WGL_ALPHA_BITS_ARB,
+            8,
+            WGL_DEPTH_BITS_ARB,
+            self.depth_bits as i32,
+            WGL_STENCIL_BITS_ARB,
+            self.stencil_bits as i32,
+        ];
+        if self.srgb {
+            attributes.extend([WGL_FRAMEBUFFER_SRGB_CAPABLE_ARB, 1]);
+        }
+        if self.samples > 1 {
+            attributes.extend([
+                WGL_SAMPLE_BUFFERS_ARB,
+                1,
+                WGL_SAMPLES_ARB,
+                self.samples as i32,
+            ]);
+        }
+        attributes.push(0);
+        attributes
+    }
+
+    fn context_attributes(&self) -> Vec<i32> {
+        let profile = if self.core_profile {
+            WGL_CONTEXT_CORE_PROFILE_BIT_ARB
+        } else {
+            WGL_CONTEXT_COMPATIBILITY_PROFILE_BIT_ARB
+        };
+        let flags = if self.debug {
+            WGL_CONTEXT_DEBUG_BIT_ARB
+        } else {
+            0
+        };
+        vec![
+            WGL_CONTEXT_MAJOR_VERSION_ARB,
+            self.version.0 as i32,
+            WGL_CONTEXT_MINOR_VERSION_ARB,
+            self.version.1 as i32,
+            WGL_CONTEXT_PROFILE_MASK_ARB,
+            profile,
+            WGL_CONTEXT_FLAGS_ARB,
+            flags,
+            0,
+        ]
+    }
+}
+
+/// An OpenGL context rendering to a window. The context is deleted with
+/// the window, after which its methods fail.
+///
+/// The window's class needs `CS_OWNDC`, see
+/// [`WindowClass::with_style`](crate::window::WindowClass::with_style), as
+/// OpenGL keeps using the device context the context was made with.
+#[derive(Debug)]
+pub struct GlContext {
+    window: WindowsWindow,
+    hdc: HDC,
+    hglrc: HGLRC,
+    swap_interval: Option<SwapIntervalExtFn>,
+}
+
+impl GlContext {
+    /// Picks a pixel format for `config` and creates the context. A window's
+    /// pixel format can only be set once, so one context per window.
+    pub fn new(window: WindowsWindow, config: &GlConfig) -> Result<Self> {
+        let Some(state) = (unsafe { window_state(window.hwnd()) }) else {
+            bail!("Not a paneless window");
+        };
+        if state.gl_context.get().is_some() {
+            bail!("The window already has an OpenGL context");
+        }
+        let class_style = unsafe { GetClassLongPtrW(window.hwnd(), GCL_STYLE) } as u32;
+        if class_style & CS_OWNDC.0 == 0 {
+            bail!("The window's class needs CS_OWNDC for an OpenGL context");
+        }
+        let extensions = Extensions::load()?;
+
+        let hdc = unsafe { GetDC(window.hwnd()) };
+        if hdc.is_invalid() {
+            bail!("Could not get the device context of the window");
+        }
+        let hglrc =
+            unsafe { create_context(hdc, config, &extensions) }.inspect_err(|_| unsafe {
+                ReleaseDC(window.hwnd(), hdc);
+            })?;
+        state.gl_context.set(Some(hglrc));
+        let mut context = Self {
+            window,
+            hdc,
+            hglrc,
+            swap_interval: None,
+        };
+        context.make_current()?;
+        // Only resolvable with a current context
+        context.swap_interval = unsafe { wglGetProcAddress(s!("wglSwapIntervalEXT")) }
+            .map(|function| unsafe { mem::transmute::<_, SwapIntervalExtFn>(function) });
+        Ok(context)
+    }
+
+    pub fn window(&self) -> WindowsWindow {
+        self.window
+    }
+
+    /// Makes the context current on the calling thread.
+    pub fn make_current(&self) -> Result<()> {
+        self.check_alive()?;
+        unsafe { wglMakeCurrent(self.hdc, self.hglrc)? };
+        Ok(())
+    }
+
+    /// Leaves the calling thread without a current context, e.g. before
+    /// making this one current on another thread.
+    pub fn make_not_current(&self) -> Result<()> {
+        if self.is_current() {
+            unsafe { wglMakeCurrent(HDC::default(), HGLRC::default())? };
+        }
+        Ok(())
+    }
+
+    pub fn is_current(&self) -> bool {
+        (unsafe { wglGetCurrentContext() }) == self.hglrc
+    }
+
+    /// Presents the back buffer.
+    pub fn swap_buffers(&self) -> Result<()> {
+        self.check_alive()?;
+        unsafe { SwapBuffers(self.hdc)? };
+        Ok(())
+    }
+
+    /// Waits for `interval` vertical blanks on each swap, 0 to not wait.
+    /// Negative values tear when a frame is late, if the driver supports
+    /// it. Applies to the current context.
+    pub fn set_swap_interval(&self, interval: i32) -> Result<()> {
+        self.check_alive()?;
+        let Some(swap_interval) = self.swap_interval else {
+            bail!("The driver doesn't support WGL_EXT_swap_control");
+        };
+        if !unsafe { swap_interval(interval) }.as_bool() {
+            bail!("Could not set the swap interval to {}", interval);
+        }
+        Ok(())
+    }
+
+    /// Address of an OpenGL function, null if the driver doesn't have it.
+    /// For loaders like `gl` or `glow`, with the context current.
+    pub fn get_proc_address(&self, name: &str) -> *const c_void {
+        let Ok(name) = CString::new(name) else {
+            return ptr::null();
+        };
+        let name = PCSTR(name.as_ptr() as *const u8);
+        unsafe {
+            // OpenGL 1.1 functions are only exported by opengl32.dll
+            wglGetProcAddress(name)
+                .filter(|function| !matches!(*function as usize, 1..=3 | usize::MAX))
+                .or_else(|| {
+                    let opengl = GetModuleHandleW(w!("opengl32.dll")).ok()?;
+                    GetProcAddress(opengl, name)
+                })
+                .map_or(ptr::null(), |function| function as *const c_void)
+        }
+    }
+
+    fn check_alive(&self) -> Result<()> {
+        let alive = unsafe { window_state(self.window.hwnd()) }
+            .is_some_and(|state| state.gl_context.get() == Some(self.hglrc));
+        if !alive {
+            bail!("The window of the OpenGL context was destroyed");
+        }
+        Ok(())
+    }
+}
+
+impl Drop for GlContext {
+    fn drop(&mut self) {
+        if self.check_alive().is_ok() {
+            self.window.destroy_gl_context();
+            unsafe {
+                ReleaseDC(self.window.hwnd(), self.hdc);
+            }
+        }
+    }
+}
+
+impl WindowsWindow {
+    /// Deletes the window's OpenGL context, when the window is destroyed or
+    /// its [`GlContext`] dropped.
+    pub(crate) fn destroy_gl_context(&self) {
+        let Some(hglrc) =
+            unsafe { window_state(self.hwnd()) }.and_then(|state| state.gl_context.take())
+        else {
+            return;
+        };
+        unsafe {
+            if wglGetCurrentContext() == hglrc {
+                let _ = wglMakeCurrent(HDC::default(), HGLRC::default());
+            }
+            let _ = wglDeleteContext(hglrc);
+        }
+    }
+}
+
+/// Sets the pixel format of `hdc` and creates the context, through the ARB
+/// extensions where available.
+unsafe fn create_context(hdc: HDC, config: &GlConfig, extensions: &Extensions) -> Result<HGLRC> {
+    let format = match extensions.choose_pixel_format {
+        Some(choose_pixel_format) => {
+            let attributes = config.pixel_format_attributes();
+            let mut format = 0;
+            let mut count = 0;
+            let found = choose_pixel_format(
+                hdc,
+                attributes.as_ptr(),
+                ptr::null(),
+                1,
+                &mut format,
+                &mut count,
+            );
+            if !found.as_bool() || count == 0 {
+                bail!("No pixel format matches {:?}", config);
+            }
+            format
+        }
+        None => ChoosePixelFormat(hdc, &legacy_pixel_format(config)),
+    };
+    let mut descriptor = PIXELFORMATDESCRIPTOR::default();
+    DescribePixelFormat(
+        hdc,
+        format,
+        mem::size_of::<PIXELFORMATDESCRIPTOR>() as u32,
+        Some(&mut descriptor),
+    );
+    SetPixelFormat(hdc, format, &descriptor)?;
+
+    match extensions.create_context_attribs {
+        Some(create_context_attribs) => {
+            let attributes = config.context_attributes();
+            let hglrc = create_context_attribs(hdc, HGLRC::default(), attributes.as_ptr());
+            if hglrc.is_invalid() {
+                bail!(
+                    "Could not create an OpenGL {}.{} context",
+                    config.version.0,
+                    config.version.1
+                );
+            }
+            Ok(hglrc)
+        }
+        None => Ok(wglCreateContext(hdc)?),
+    }
+}
+
+fn legacy_pixel_format(config: &GlConfig) -> PIXELFORMATDESCRIPTOR {
+    PIXELFORMATDESCRIPTOR {
+        nSize: mem::size_of::<PIXELFORMATDESCRIPTOR>() as u16,
+        nVersion: 1,
+        dwFlags: PFD_DRAW_TO_WINDOW | PFD_SUPPORT_OPENGL | PFD_DOUBLEBUFFER,
+        iPixelType: PFD_TYPE_RGBA,
+        cColorBits: 32,
+        cAlphaBits: 8,
+        cDepthBits: config.depth_bits,
+        cStencilBits: config.stencil_bits,
+        iLayerType: PFD_MAIN_PLANE.0 as u8,
+        ..Default::default()
+    }
+}
+
+/// The WGL extensions creating modern contexts, which can only be queried
+/// with a context current.
+struct Extensions {
+    choose_pixel_format: Option<ChoosePixelFormatArbFn>,
+    create_context_attribs: Option<CreateContextAttribsArbFn>,
+}
+
+impl Extensions {
+    /// Loads them with a legacy context on a hidden window, the pixel format
+    /// of the real window being set once and for all.
+    fn load() -> Result<Self> {
+        let hwnd = unsafe {
+            CreateWindowExW(
+                WINDOW_EX_STYLE::default(),
+                w!("STATIC"),
+                w!(""),
+                WS_OVERLAPPEDWINDOW,
+                0,
+                0,
+                1,
+                1,
+                HWND::default(),
+                HMENU::default(),
+                get_instance_handle(),
+                None,
+            )?
+        };
+        let extensions = unsafe { Self::load_with(hwnd) };
+        unsafe {
+            let _ = DestroyWindow(hwnd);
+        }
+        extensions
+    }
+
+    unsafe fn load_with(hwnd: HWND) -> Result<Self> {
+        let hdc = GetDC(hwnd);
+        let descriptor = legacy_pixel_format(&GlConfig::default());
+        SetPixelFormat(hdc, ChoosePixelFormat(hdc, &descriptor), &descriptor)?;
+        let hglrc = wglCreateContext(hdc)?;
+        let previous = (wglGetCurrentDC(), wglGetCurrentContext());
+        let extensions = wglMakeCurrent(hdc, hglrc).map(|()| Self {
+            choose_pixel_format: wglGetProcAddress(s!("wglChoosePixelFormatARB"))
+                .map(|function| mem::transmute::<_, ChoosePixelFormatArbFn>(function)),
+            create_context_attribs: wglGetProcAddress(s!("wglCreateContextAttribsARB"))
+                .map(|function| mem::transmute::<_, CreateContextAttribsArbFn>(function)),
+        });
+        let _ = wglMakeCurrent(previous.0, previous.1);
+        let _ = wglDeleteContext(hglrc);
+        ReleaseDC(hwnd, hdc);
+        Ok(extensions?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_attributes() {
+        let config = GlConfig::new()
+            .with_version(4, 6)
+            .with_samples(4)
+            .with_srgb(false);
+        let attributes = config.pixel_format_attributes();
+        assert_eq!(attributes.last(), Some(&0));
+        assert!(attributes
+            .windows(2)
+            .any(|pair| pair == [WGL_SAMPLES_ARB, 4]));
+        assert!(!attributes.contains(&WGL_FRAMEBUFFER_SRGB_CAPABLE_ARB));
+        assert_eq!(
+            config.context_attributes()[..4],
+            [
+                WGL_CONTEXT_MAJOR_VERSION_ARB,
+                4,
+                WGL_CONTEXT_MINOR_VERSION_ARB,
+                6
+            ]
+        );
+    }
+}
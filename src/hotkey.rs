@@ -0,0 +1,150 @@
+use std::sync::atomic::{AtomicI32, Ordering};
+
+use anyhow::{bail, Result};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use windows::Win32::{
+    Foundation::HWND,
+    UI::Input::KeyboardAndMouse::{
+        RegisterHotKey, UnregisterHotKey, HOT_KEY_MODIFIERS, MOD_ALT, MOD_CONTROL, MOD_NOREPEAT,
+        MOD_SHIFT, MOD_WIN,
+    },
+};
+
+use crate::{
+    event_loop::EventLoop,
+    keyboard::{KeyCode, KeyboardLayout, ModifiersState},
+};
+
+/// Ids above are reserved for shared libraries.
+const MAX_HOTKEY_ID: i32 = 0xbfff;
+
+/// Ids are per thread's target window, shared by every manager of a loop.
+/// Counting for the whole process keeps them apart.
+static NEXT_HOTKEY_ID: AtomicI32 = AtomicI32::new(0);
+
+/// Identifies a registered hotkey in [`Event::GlobalHotkey`](crate::event::Event::GlobalHotkey).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HotkeyId(pub(crate) i32);
+
+/// A key combination, see [`HotkeyManager::register`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Hotkey {
+    key: KeyCode,
+    modifiers: ModifiersState,
+    repeat: bool,
+}
+
+impl Hotkey {
+    /// The physical `key`, mapped to the active layout when registering.
+    pub fn new(key: KeyCode) -> Self {
+        Self {
+            key,
+            modifiers: ModifiersState::default(),
+            repeat: false,
+        }
+    }
+
+    pub fn with_modifiers(mut self, modifiers: ModifiersState) -> Self {
+        self.modifiers = modifiers;
+        self
+    }
+
+    /// Fires again while the keys are held, e.g. for push-to-talk. Off by
+    /// default.
+    pub fn with_repeat(mut self, repeat: bool) -> Self {
+        self.repeat = repeat;
+        self
+    }
+
+    fn modifiers(&self) -> HOT_KEY_MODIFIERS {
+        let mut flags = HOT_KEY_MODIFIERS::default();
+        for (held, flag) in [
+            (self.modifiers.shift, MOD_SHIFT),
+            (self.modifiers.control, MOD_CONTROL),
+            (self.modifiers.alt, MOD_ALT),
+            (self.modifiers.super_key, MOD_WIN),
+            (!self.repeat, MOD_NOREPEAT),
+        ] {
+            if held {
+                flags |= flag;
+            }
+        }
+        flags
+    }
+}
+
+/// System-wide hotkeys, delivered as
+/// [`Event::GlobalHotkey`](crate::event::Event::GlobalHotkey) by an event
+/// loop even while another application has the focus. The hotkeys are
+/// unregistered when the manager is dropped, which must happen on the
+/// loop's thread and before the loop is dropped.
+pub struct HotkeyManager {
+    target: HWND,
+    registered: Vec<HotkeyId>,
+}
+
+impl HotkeyManager {
+    pub fn new<T>(event_loop: &EventLoop<T>) -> Self {
+        Self {
+            target: event_loop.target_hwnd(),
+            registered: Vec::new(),
+        }
+    }
+
+    /// Fails if another application already registered the combination.
+    pub fn register(&mut self, hotkey: Hotkey) -> Result<HotkeyId> {
+        let Some(virtual_key) = KeyboardLayout::current().virtual_key(hotkey.key) else {
+            bail!("{:?} has no virtual key in the current layout", hotkey.key);
+        };
+        let Ok(id) = NEXT_HOTKEY_ID.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |id| {
+            (id <= MAX_HOTKEY_ID).then_some(id + 1)
+        }) else {
+            bail!("Too many hotkeys were registered");
+        };
+        let id = HotkeyId(id);
+        unsafe { RegisterHotKey(self.target, id.0, hotkey.modifiers(), virtual_key as u32)? };
+        self.registered.push(id);
+        Ok(id)
+    }
+
+    pub fn unregister(&mut self, id: HotkeyId) -> Result<()> {
+        let Some(index) = self
+            .registered
+            .iter()
+            .position(|registered| *registered == id)
+        else {
+            bail!("Hotkey {:?} is not registered", id);
+        };
+        self.registered.swap_remove(index);
+        unsafe { UnregisterHotKey(self.target, id.0)? };
+        Ok(())
+    }
+}
+
+impl Drop for HotkeyManager {
+    fn drop(&mut self) {
+        for id in self.registered.drain(..) {
+            unsafe {
+                let _ = UnregisterHotKey(self.target, id.0);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_modifiers() {
+        let hotkey = Hotkey::new(KeyCode::KeyK).with_modifiers(ModifiersState {
+            control: true,
+            super_key: true,
+            ..Default::default()
+        });
+        assert_eq!(hotkey.modifiers(), MOD_CONTROL | MOD_WIN | MOD_NOREPEAT);
+        assert_eq!(hotkey.with_repeat(true).modifiers(), MOD_CONTROL | MOD_WIN);
+    }
+}
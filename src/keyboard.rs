@@ -0,0 +1,633 @@
+pub mod hook;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use windows::Win32::UI::Input::KeyboardAndMouse::*;
+
+pub use hook::{HookedKey, KeyboardHook};
+
+/// A key of a standard keyboard. Variants are named after what the key
+/// produces on a US layout, as in the UI Events `code` values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum KeyCode {
+    KeyA,
+    KeyB,
+    KeyC,
+    KeyD,
+    KeyE,
+    KeyF,
+    KeyG,
+    KeyH,
+    KeyI,
+    KeyJ,
+    KeyK,
+    KeyL,
+    KeyM,
+    KeyN,
+    KeyO,
+    KeyP,
+    KeyQ,
+    KeyR,
+    KeyS,
+    KeyT,
+    KeyU,
+    KeyV,
+    KeyW,
+    KeyX,
+    KeyY,
+    KeyZ,
+    Digit0,
+    Digit1,
+    Digit2,
+    Digit3,
+    Digit4,
+    Digit5,
+    Digit6,
+    Digit7,
+    Digit8,
+    Digit9,
+    Backquote,
+    Minus,
+    Equal,
+    BracketLeft,
+    BracketRight,
+    Backslash,
+    Semicolon,
+    Quote,
+    Comma,
+    Period,
+    Slash,
+    /// The extra key next to the left Shift on ISO keyboards.
+    IntlBackslash,
+    Escape,
+    Backspace,
+    Tab,
+    Enter,
+    Space,
+    CapsLock,
+    ShiftLeft,
+    ShiftRight,
+    ControlLeft,
+    ControlRight,
+    AltLeft,
+    AltRight,
+    SuperLeft,
+    SuperRight,
+    ContextMenu,
+    Insert,
+    Delete,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    ArrowUp,
+    ArrowDown,
+    ArrowLeft,
+    ArrowRight,
+    PrintScreen,
+    ScrollLock,
+    Pause,
+    NumLock,
+    Numpad0,
+    Numpad1,
+    Numpad2,
+    Numpad3,
+    Numpad4,
+    Numpad5,
+    Numpad6,
+    Numpad7,
+    Numpad8,
+    Numpad9,
+    NumpadAdd,
+    NumpadSubtract,
+    NumpadMultiply,
+    NumpadDivide,
+    NumpadDecimal,
+    NumpadEnter,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    F13,
+    F14,
+    F15,
+    F16,
+    F17,
+    F18,
+    F19,
+    F20,
+    F21,
+    F22,
+    F23,
+    F24,
+    /// A key without a variant, with its scancode or virtual key code.
+    Unidentified(u16),
+}
+
+impl KeyCode {
+    /// The key at the position of `scancode`, whatever the layout. Takes set
+    /// 1 scancodes with 0x100 added for extended keys, as in the lParam of
+    /// key messages.
+    pub fn from_scancode(scancode: u16) -> Self {
+        match scancode {
+            0x01e => KeyCode::KeyA,
+            0x030 => KeyCode::KeyB,
+            0x02e => KeyCode::KeyC,
+            0x020 => KeyCode::KeyD,
+            0x012 => KeyCode::KeyE,
+            0x021 => KeyCode::KeyF,
+            0x022 => KeyCode::KeyG,
+            0x023 => KeyCode::KeyH,
+            0x017 => KeyCode::KeyI,
+            0x024 => KeyCode::KeyJ,
+            0x025 => KeyCode::KeyK,
+            0x026 => KeyCode::KeyL,
+            0x032 => KeyCode::KeyM,
+            0x031 => KeyCode::KeyN,
+            0x018 => KeyCode::KeyO,
+            0x019 => KeyCode::KeyP,
+            0x010 => KeyCode::KeyQ,
+            0x013 => KeyCode::KeyR,
+            0x01f => KeyCode::KeyS,
+            0x014 => KeyCode::KeyT,
+            0x016 => KeyCode::KeyU,
+            0x02f => KeyCode::KeyV,
+            0x011 => KeyCode::KeyW,
+            0x02d => KeyCode::KeyX,
+            0x015 => KeyCode::KeyY,
+            0x02c => KeyCode::KeyZ,
+            0x00b => KeyCode::Digit0,
+            0x002 => KeyCode::Digit1,
+            0x003 => KeyCode::Digit2,
+            0x004 => KeyCode::Digit3,
+            0x005 => KeyCode::Digit4,
+            0x006 => KeyCode::Digit5,
+            0x007 => KeyCode::Digit6,
+            0x008 => KeyCode::Digit7,
+            0x009 => KeyCode::Digit8,
+            0x00a => KeyCode::Digit9,
+            0x029 => KeyCode::Backquote,
+            0x00c => KeyCode::Minus,
+            0x00d => KeyCode::Equal,
+            0x01a => KeyCode::BracketLeft,
+            0x01b => KeyCode::BracketRight,
+            0x02b => KeyCode::Backslash,
+            0x027 => KeyCode::Semicolon,
+            0x028 => KeyCode::Quote,
+            0x033 => KeyCode::Comma,
+            0x034 => KeyCode::Period,
+            0x035 => KeyCode::Slash,
+            0x056 => KeyCode::IntlBackslash,
+            0x001 => KeyCode::Escape,
+            0x00e => KeyCode::Backspace,
+            0x00f => KeyCode::Tab,
+            0x01c => KeyCode::Enter,
+            0x039 => KeyCode::Space,
+            0x03a => KeyCode::CapsLock,
+            0x02a => KeyCode::ShiftLeft,
+            0x036 => KeyCode::ShiftRight,
+            0x01d => KeyCode::ControlLeft,
+            0x11d => KeyCode::ControlRight,
+            0x038 => KeyCode::AltLeft,
+            0x138 => KeyCode::AltRight,
+            0x15b => KeyCode::SuperLeft,
+            0x15c => KeyCode::SuperRight,
+            0x15d => KeyCode::ContextMenu,
+            0x152 => KeyCode::Insert,
+            0x153 => KeyCode::Delete,
+            0x147 => KeyCode::Home,
+            0x14f => KeyCode::End,
+            0x149 => KeyCode::PageUp,
+            0x151 => KeyCode::PageDown,
+            0x148 => KeyCode::ArrowUp,
+            0x150 => KeyCode::ArrowDown,
+            0x14b => KeyCode::ArrowLeft,
+            0x14d => KeyCode::ArrowRight,
+            0x137 => KeyCode::PrintScreen,
+            0x046 => KeyCode::ScrollLock,
+            0x045 => KeyCode::Pause,
+            0x145 => KeyCode::NumLock,
+            0x052 => KeyCode::Numpad0,
+            0x04f => KeyCode::Numpad1,
+            0x050 => KeyCode::Numpad2,
+            0x051 => KeyCode::Numpad3,
+            0x04b => KeyCode::Numpad4,
+            0x04c => KeyCode::Numpad5,
+            0x04d => KeyCode::Numpad6,
+            0x047 => KeyCode::Numpad7,
+            0x048 => KeyCode::Numpad8,
+            0x049 => KeyCode::Numpad9,
+            0x04e => KeyCode::NumpadAdd,
+            0x04a => KeyCode::NumpadSubtract,
+            0x037 => KeyCode::NumpadMultiply,
+            0x135 => KeyCode::NumpadDivide,
+            0x053 => KeyCode::NumpadDecimal,
+            0x11c => KeyCode::NumpadEnter,
+            0x03b => KeyCode::F1,
+            0x03c => KeyCode::F2,
+            0x03d => KeyCode::F3,
+            0x03e => KeyCode::F4,
+            0x03f => KeyCode::F5,
+            0x040 => KeyCode::F6,
+            0x041 => KeyCode::F7,
+            0x042 => KeyCode::F8,
+            0x043 => KeyCode::F9,
+            0x044 => KeyCode::F10,
+            0x057 => KeyCode::F11,
+            0x058 => KeyCode::F12,
+            0x064 => KeyCode::F13,
+            0x065 => KeyCode::F14,
+            0x066 => KeyCode::F15,
+            0x067 => KeyCode::F16,
+            0x068 => KeyCode::F17,
+            0x069 => KeyCode::F18,
+            0x06a => KeyCode::F19,
+            0x06b => KeyCode::F20,
+            0x06c => KeyCode::F21,
+            0x06d => KeyCode::F22,
+            0x06e => KeyCode::F23,
+            0x076 => KeyCode::F24,
+            _ => KeyCode::Unidentified(scancode),
+        }
+    }
+
+    /// Inverse of [`from_scancode`](Self::from_scancode).
+    pub fn scancode(self) -> u16 {
+        match self {
+            KeyCode::Unidentified(code) => code,
+            key => (0..0x200)
+                .find(|&scancode| Self::from_scancode(scancode) == key)
+                .unwrap_or_default(),
+        }
+    }
+
+    /// The key labelled as `virtual_key` in the active layout. `scancode`
+    /// tells apart the left and right modifiers, which share a virtual key
+    /// in key messages.
+    pub fn from_virtual_key(virtual_key: u16, scancode: u16) -> Self {
+        let extended = scancode & 0x100 != 0;
+        match VIRTUAL_KEY(virtual_key) {
+            VK_SHIFT | VK_LSHIFT | VK_RSHIFT if scancode == 0x36 => KeyCode::ShiftRight,
+            VK_SHIFT | VK_LSHIFT | VK_RSHIFT => KeyCode::ShiftLeft,
+            VK_CONTROL | VK_LCONTROL | VK_RCONTROL if extended => KeyCode::ControlRight,
+            VK_CONTROL | VK_LCONTROL | VK_RCONTROL => KeyCode::ControlLeft,
+            VK_MENU | VK_LMENU | VK_RMENU if extended => KeyCode::AltRight,
+            VK_MENU | VK_LMENU | VK_RMENU => KeyCode::AltLeft,
+            VK_RETURN if extended => KeyCode::NumpadEnter,
+            VK_RETURN => KeyCode::Enter,
+            VK_A => KeyCode::KeyA,
+            VK_B => KeyCode::KeyB,
+            VK_C => KeyCode::KeyC,
+            VK_D => KeyCode::KeyD,
+            VK_E => KeyCode::KeyE,
+            VK_F => KeyCode::KeyF,
+            VK_G => KeyCode::KeyG,
+            VK_H => KeyCode::KeyH,
+            VK_I => KeyCode::KeyI,
+            VK_J => KeyCode::KeyJ,
+            VK_K => KeyCode::KeyK,
+            VK_L => KeyCode::KeyL,
+            VK_M => KeyCode::KeyM,
+            VK_N => KeyCode::KeyN,
+            VK_O => KeyCode::KeyO,
+            VK_P => KeyCode::KeyP,
+            VK_Q => KeyCode::KeyQ,
+            VK_R => KeyCode::KeyR,
+            VK_S => KeyCode::KeyS,
+            VK_T => KeyCode::KeyT,
+            VK_U => KeyCode::KeyU,
+            VK_V => KeyCode::KeyV,
+            VK_W => KeyCode::KeyW,
+            VK_X => KeyCode::KeyX,
+            VK_Y => KeyCode::KeyY,
+            VK_Z => KeyCode::KeyZ,
+            VK_0 => KeyCode::Digit0,
+            VK_1 => KeyCode::Digit1,
+            VK_2 => KeyCode::Digit2,
+            VK_3 => KeyCode::Digit3,
+            VK_4 => KeyCode::Digit4,
+            VK_5 => KeyCode::Digit5,
+            VK_6 => KeyCode::Digit6,
+            VK_7 => KeyCode::Digit7,
+            VK_8 => KeyCode::Digit8,
+            VK_9 => KeyCode::Digit9,
+            VK_OEM_3 => KeyCode::Backquote,
+            VK_OEM_MINUS => KeyCode::Minus,
+            VK_OEM_PLUS => KeyCode::Equal,
+            VK_OEM_4 => KeyCode::BracketLeft,
+            VK_OEM_6 => KeyCode::BracketRight,
+            VK_OEM_5 => KeyCode::Backslash,
+            VK_OEM_1 => KeyCode::Semicolon,
+            VK_OEM_7 => KeyCode::Quote,
+            VK_OEM_COMMA => KeyCode::Comma,
+            VK_OEM_PERIOD => KeyCode::Period,
+            VK_OEM_2 => KeyCode::Slash,
+            VK_OEM_102 => KeyCode::IntlBackslash,
+            VK_ESCAPE => KeyCode::Escape,
+            VK_BACK => KeyCode::Backspace,
+            VK_TAB => KeyCode::Tab,
+            VK_SPACE => KeyCode::Space,
+            VK_CAPITAL => KeyCode::CapsLock,
+            VK_LWIN => KeyCode::SuperLeft,
+            VK_RWIN => KeyCode::SuperRight,
+            VK_APPS => KeyCode::ContextMenu,
+            VK_INSERT => KeyCode::Insert,
+            VK_DELETE => KeyCode::Delete,
+            VK_HOME => KeyCode::Home,
+            VK_END => KeyCode::End,
+            VK_PRIOR => KeyCode::PageUp,
+            VK_NEXT => KeyCode::PageDown,
+            VK_UP => KeyCode::ArrowUp,
+            VK_DOWN => KeyCode::ArrowDown,
+            VK_LEFT => KeyCode::ArrowLeft,
+            VK_RIGHT => KeyCode::ArrowRight,
+            VK_SNAPSHOT => KeyCode::PrintScreen,
+            VK_SCROLL => KeyCode::ScrollLock,
+            VK_PAUSE => KeyCode::Pause,
+            VK_NUMLOCK => KeyCode::NumLock,
+            VK_NUMPAD0 => KeyCode::Numpad0,
+            VK_NUMPAD1 => KeyCode::Numpad1,
+            VK_NUMPAD2 => KeyCode::Numpad2,
+            VK_NUMPAD3 => KeyCode::Numpad3,
+            VK_NUMPAD4 => KeyCode::Numpad4,
+            VK_NUMPAD5 => KeyCode::Numpad5,
+            VK_NUMPAD6 => KeyCode::Numpad6,
+            VK_NUMPAD7 => KeyCode::Numpad7,
+            VK_NUMPAD8 => KeyCode::Numpad8,
+            VK_NUMPAD9 => KeyCode::Numpad9,
+            VK_ADD => KeyCode::NumpadAdd,
+            VK_SUBTRACT => KeyCode::NumpadSubtract,
+            VK_MULTIPLY => KeyCode::NumpadMultiply,
+            VK_DIVIDE => KeyCode::NumpadDivide,
+            VK_DECIMAL => KeyCode::NumpadDecimal,
+            VK_F1 => KeyCode::F1,
+            VK_F2 => KeyCode::F2,
+            VK_F3 => KeyCode::F3,
+            VK_F4 => KeyCode::F4,
+            VK_F5 => KeyCode::F5,
+            VK_F6 => KeyCode::F6,
+            VK_F7 => KeyCode::F7,
+            VK_F8 => KeyCode::F8,
+            VK_F9 => KeyCode::F9,
+            VK_F10 => KeyCode::F10,
+            VK_F11 => KeyCode::F11,
+            VK_F12 => KeyCode::F12,
+            VK_F13 => KeyCode::F13,
+            VK_F14 => KeyCode::F14,
+            VK_F15 => KeyCode::F15,
+            VK_F16 => KeyCode::F16,
+            VK_F17 => KeyCode::F17,
+            VK_F18 => KeyCode::F18,
+            VK_F19 => KeyCode::F19,
+            VK_F20 => KeyCode::F20,
+            VK_F21 => KeyCode::F21,
+            VK_F22 => KeyCode::F22,
+            VK_F23 => KeyCode::F23,
+            VK_F24 => KeyCode::F24,
+            _ => KeyCode::Unidentified(virtual_key),
+        }
+    }
+}
+
+/// Modifier keys held down, either side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ModifiersState {
+    pub shift: bool,
+    pub control: bool,
+    pub alt: bool,
+    /// The Windows key.
+    pub super_key: bool,
+}
+
+impl ModifiersState {
+    /// The modifiers as of the message being handled.
+    pub(crate) fn current() -> Self {
+        let down = |key: VIRTUAL_KEY| unsafe { GetKeyState(key.0 as i32) } < 0;
+        Self {
+            shift: down(VK_SHIFT),
+            control: down(VK_CONTROL),
+            alt: down(VK_MENU),
+            super_key: down(VK_LWIN) || down(VK_RWIN),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// An input locale: a language and the keyboard layout typing it, e.g. French
+/// with AZERTY.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct KeyboardLayout(isize);
+
+impl KeyboardLayout {
+    /// The layout active on the calling thread, the event loop's for the
+    /// windows it runs.
+    pub fn current() -> Self {
+        Self::from_hkl(unsafe { GetKeyboardLayout(0) })
+    }
+
+    pub(crate) fn from_hkl(hkl: HKL) -> Self {
+        Self(hkl.0 as isize)
+    }
+
+    pub fn hkl(&self) -> HKL {
+        HKL(self.0 as *mut _)
+    }
+
+    /// Language identifier of the input locale, e.g. 0x040c for French
+    /// (France).
+    pub fn language_id(&self) -> u16 {
+        (self.0 & 0xffff) as u16
+    }
+
+    /// The virtual key `key` sends in this layout.
+    pub(crate) fn virtual_key(&self, key: KeyCode) -> Option<u16> {
+        // Pause sends NumLock's scancode, which then maps to NumLock
+        match key {
+            KeyCode::Pause => return Some(VK_PAUSE.0),
+            KeyCode::NumLock => return Some(VK_NUMLOCK.0),
+            _ => (),
+        }
+        let scancode = key.scancode();
+        // Extended scancodes are prefixed with 0xe0 here
+        let prefixed = match scancode & 0x100 {
+            0 => scancode as u32,
+            _ => 0xe000 | (scancode & 0xff) as u32,
+        };
+        let virtual_key = unsafe { MapVirtualKeyExW(prefixed, MAPVK_VSC_TO_VK_EX, self.hkl()) };
+        (virtual_key != 0).then_some(virtual_key as u16)
+    }
+
+    /// The character `key` types in this layout with `modifiers` held, e.g.
+    /// to label a shortcut bound to a physical key. Control characters give
+    /// `None`. Unlike typing the key, a dead key doesn't affect the next
+    /// keystroke (Windows 10 1607 and newer).
+    pub fn key_to_char(&self, key: KeyCode, modifiers: ModifiersState) -> Option<KeyChar> {
+        let scancode = key.scancode();
+        let virtual_key = self.virtual_key(key)? as u32;
+
+        let mut key_state = [0u8; 256];
+        let mut press = |key: VIRTUAL_KEY| key_state[key.0 as usize] = 0x80;
+        if modifiers.shift {
+            press(VK_SHIFT);
+        }
+        if modifiers.control {
+            press(VK_CONTROL);
+        }
+        if modifiers.alt {
+            press(VK_MENU);
+        }
+        let mut buffer = [0u16; 8];
+        // Bit 2 leaves the dead key state of the thread alone
+        let length = unsafe {
+            ToUnicodeEx(
+                virtual_key,
+                scancode as u32,
+                &key_state,
+                &mut buffer,
+                1 << 2,
+                self.hkl(),
+            )
+        };
+        // A dead key writes its spacing form
+        let (dead, length) = match length {
+            -1 => (true, 1),
+            1.. => (false, length as usize),
+            _ => return None,
+        };
+        let character = char::decode_utf16(buffer[..length].iter().copied())
+            .next()?
+            .ok()
+            .filter(|character| !character.is_control())?;
+        Some(if dead {
+            KeyChar::Dead(character)
+        } else {
+            KeyChar::Char(character)
+        })
+    }
+}
+
+/// The state of every key as of the last keyboard message the calling
+/// thread handled, for polling input instead of tracking events. Unlike
+/// events, keys pressed while none of the thread's windows had the focus
+/// may show up.
+pub fn keyboard_snapshot() -> KeyboardSnapshot {
+    let mut state = [0; 256];
+    unsafe {
+        let _ = GetKeyboardState(&mut state);
+    }
+    KeyboardSnapshot {
+        state,
+        layout: KeyboardLayout::current(),
+    }
+}
+
+/// Result of [`keyboard_snapshot`].
+#[derive(Debug, Clone)]
+pub struct KeyboardSnapshot {
+    // Indexed by virtual key
+    state: [u8; 256],
+    layout: KeyboardLayout,
+}
+
+impl KeyboardSnapshot {
+    pub fn is_pressed(&self, key: KeyCode) -> bool {
+        self.key_state(key) & 0x80 != 0
+    }
+
+    /// Whether a lock key like CapsLock is on.
+    pub fn is_toggled(&self, key: KeyCode) -> bool {
+        self.key_state(key) & 0x01 != 0
+    }
+
+    fn key_state(&self, key: KeyCode) -> u8 {
+        self.layout
+            .virtual_key(key)
+            .map_or(0, |virtual_key| self.state[virtual_key as usize & 0xff])
+    }
+}
+
+/// Result of [`KeyboardLayout::key_to_char`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyChar {
+    Char(char),
+    /// A dead key, like `^` on French layouts, which combines with the next
+    /// character typed.
+    Dead(char),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_scancodes_to_positions() {
+        assert_eq!(KeyCode::from_scancode(0x10), KeyCode::KeyQ);
+        assert_eq!(KeyCode::from_scancode(0x1c), KeyCode::Enter);
+        assert_eq!(KeyCode::from_scancode(0x11c), KeyCode::NumpadEnter);
+        assert_eq!(KeyCode::from_scancode(0x145), KeyCode::NumLock);
+        assert_eq!(KeyCode::from_scancode(0x45), KeyCode::Pause);
+        assert_eq!(KeyCode::from_scancode(0x7f), KeyCode::Unidentified(0x7f));
+    }
+
+    #[test]
+    fn tells_left_and_right_modifiers_apart() {
+        assert_eq!(
+            KeyCode::from_virtual_key(VK_SHIFT.0, 0x2a),
+            KeyCode::ShiftLeft
+        );
+        assert_eq!(
+            KeyCode::from_virtual_key(VK_SHIFT.0, 0x36),
+            KeyCode::ShiftRight
+        );
+        assert_eq!(
+            KeyCode::from_virtual_key(VK_CONTROL.0, 0x11d),
+            KeyCode::ControlRight
+        );
+        assert_eq!(KeyCode::from_virtual_key(VK_MENU.0, 0x38), KeyCode::AltLeft);
+        // Q on an AZERTY layout
+        assert_eq!(KeyCode::from_virtual_key(VK_A.0, 0x10), KeyCode::KeyA);
+    }
+
+    #[test]
+    fn maps_keys_back_to_scancodes() {
+        for scancode in [0x10, 0x11c, 0x145, 0x45] {
+            assert_eq!(KeyCode::from_scancode(scancode).scancode(), scancode);
+        }
+        assert_eq!(KeyCode::Unidentified(0x7f).scancode(), 0x7f);
+    }
+
+    #[test]
+    fn reads_snapshots() {
+        let mut state = [0; 256];
+        state[VK_PAUSE.0 as usize] = 0x80;
+        state[VK_NUMLOCK.0 as usize] = 0x01;
+        let snapshot = KeyboardSnapshot {
+            state,
+            layout: KeyboardLayout::current(),
+        };
+        assert!(snapshot.is_pressed(KeyCode::Pause));
+        assert!(!snapshot.is_toggled(KeyCode::Pause));
+        assert!(snapshot.is_toggled(KeyCode::NumLock));
+        assert!(!snapshot.is_pressed(KeyCode::NumLock));
+    }
+
+    #[test]
+    fn reads_the_language_of_a_layout() {
+        let layout = KeyboardLayout::from_hkl(HKL(0x040c_040c as *mut _));
+        assert_eq!(layout.language_id(), 0x040c);
+    }
+}
@@ -0,0 +1,164 @@
+use std::cell::Cell;
+
+use anyhow::{bail, Result};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use windows::Win32::{
+    Foundation::{HWND, LPARAM, LRESULT, WPARAM},
+    UI::WindowsAndMessaging::{
+        CallNextHookEx, SetWindowsHookExW, UnhookWindowsHookEx, HC_ACTION, HHOOK, KBDLLHOOKSTRUCT,
+        LLKHF_EXTENDED, LLKHF_INJECTED, LLKHF_UP, WH_KEYBOARD_LL,
+    },
+};
+
+use super::KeyCode;
+use crate::{
+    event::{ElementState, Event},
+    event_loop::{
+        windows::{catch_panic, dispatch_event},
+        EventLoop,
+    },
+    window::windows::get_instance_handle,
+};
+
+/// Decides whether a key is kept from every application.
+type HookFilter = Box<dyn FnMut(&HookedKey) -> bool>;
+
+thread_local! {
+    // Taken while it runs, like window message hooks.
+    static FILTER: Cell<Option<HookFilter>> = const { Cell::new(None) };
+    // Event loop window the keys are delivered with, while installed.
+    static TARGET: Cell<Option<HWND>> = const { Cell::new(None) };
+}
+
+/// A keystroke seen by a [`KeyboardHook`], before any application.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HookedKey {
+    pub physical_key: KeyCode,
+    pub virtual_key: u16,
+    pub state: ElementState,
+    /// Sent by a program, e.g. with `SendInput`, rather than typed.
+    pub injected: bool,
+}
+
+impl From<&KBDLLHOOKSTRUCT> for HookedKey {
+    fn from(info: &KBDLLHOOKSTRUCT) -> Self {
+        let extended = if info.flags.0 & LLKHF_EXTENDED.0 != 0 {
+            0x100
+        } else {
+            0
+        };
+        Self {
+            physical_key: KeyCode::from_scancode((info.scanCode & 0xff) as u16 | extended),
+            virtual_key: info.vkCode as u16,
+            state: if info.flags.0 & LLKHF_UP.0 != 0 {
+                ElementState::Released
+            } else {
+                ElementState::Pressed
+            },
+            injected: info.flags.0 & LLKHF_INJECTED.0 != 0,
+        }
+    }
+}
+
+/// A low level keyboard hook, for what hotkeys can't do: remapping the
+/// Windows key, or blocking keys in a kiosk. Every keystroke of the session
+/// goes through `filter` and is delivered as [`Event::HookedKey`]. Removed
+/// when dropped.
+///
+/// The system skips the hook if `filter` takes too long (about a second by
+/// default), and the event loop has to keep running for keys to arrive at
+/// all, everywhere. A panic in `filter` resumes from the event loop, like one
+/// in the event handler.
+pub struct KeyboardHook {
+    hook: HHOOK,
+}
+
+impl KeyboardHook {
+    /// Installs the hook of the event loop's thread, `filter` returns `true`
+    /// to swallow a key before any application sees it. One hook per thread.
+    pub fn install<T>(
+        event_loop: &EventLoop<T>,
+        filter: impl FnMut(&HookedKey) -> bool + 'static,
+    ) -> Result<Self> {
+        if TARGET.with(Cell::get).is_some() {
+            bail!("A keyboard hook is already installed on this thread");
+        }
+        let hook = unsafe {
+            SetWindowsHookExW(
+                WH_KEYBOARD_LL,
+                Some(keyboard_hook_procedure),
+                get_instance_handle(),
+                0,
+            )?
+        };
+        TARGET.with(|target| target.set(Some(event_loop.target_hwnd())));
+        FILTER.with(|current| current.set(Some(Box::new(filter))));
+        Ok(Self { hook })
+    }
+}
+
+impl Drop for KeyboardHook {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = UnhookWindowsHookEx(self.hook);
+        }
+        TARGET.with(|target| target.set(None));
+        FILTER.with(|current| current.set(None));
+    }
+}
+
+unsafe extern "system" fn keyboard_hook_procedure(
+    code: i32,
+    w_param: WPARAM,
+    l_param: LPARAM,
+) -> LRESULT {
+    if code == HC_ACTION as i32 {
+        let key = HookedKey::from(&*(l_param.0 as *const KBDLLHOOKSTRUCT));
+        let swallow = FILTER.with(|current| {
+            let mut filter = current.take()?;
+            // A key whose filter panicked goes through, the panic resumes
+            // from the event loop
+            let swallow = catch_panic(|| filter(&key));
+            // Unless the filter dropped the hook
+            if TARGET.with(Cell::get).is_some() {
+                current.set(Some(filter));
+            }
+            swallow
+        });
+        if let (Some(swallow), Some(target)) = (swallow, TARGET.with(Cell::get)) {
+            dispatch_event(target, Event::HookedKey(key));
+            if swallow {
+                return LRESULT(1);
+            }
+        }
+    }
+    CallNextHookEx(HHOOK::default(), code, w_param, l_param)
+}
+
+#[cfg(test)]
+mod tests {
+    use windows::Win32::UI::WindowsAndMessaging::KBDLLHOOKSTRUCT_FLAGS;
+
+    use super::*;
+
+    #[test]
+    fn reads_hooked_keys() {
+        let info = KBDLLHOOKSTRUCT {
+            vkCode: 0x5b,
+            scanCode: 0x5b,
+            flags: KBDLLHOOKSTRUCT_FLAGS(LLKHF_EXTENDED.0 | LLKHF_UP.0),
+            ..Default::default()
+        };
+        assert_eq!(
+            HookedKey::from(&info),
+            HookedKey {
+                physical_key: KeyCode::SuperLeft,
+                virtual_key: 0x5b,
+                state: ElementState::Released,
+                injected: false,
+            }
+        );
+    }
+}
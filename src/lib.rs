@@ -1,5 +1,22 @@
-pub mod window;
+#[cfg(feature = "d2d")]
+pub mod d2d;
+#[cfg(feature = "d3d11")]
+pub mod d3d11;
+#[cfg(feature = "d3d12")]
+pub mod d3d12;
+pub mod event;
+pub mod event_loop;
+pub mod gamepad;
+#[cfg(feature = "gl")]
+pub mod gl;
+pub mod hotkey;
+pub mod keyboard;
+#[cfg(feature = "record")]
+pub mod record;
 pub mod utils;
+#[cfg(feature = "vulkan")]
+pub mod vulkan;
+pub mod window;
 
 pub fn add(left: u64, right: u64) -> u64 {
     left + right
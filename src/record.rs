@@ -0,0 +1,151 @@
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::Path,
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::event::Event;
+
+/// An event together with the time it was delivered, relative to the start of
+/// the recording.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedEvent<T = ()> {
+    pub time: Duration,
+    pub event: Event<T>,
+}
+
+#[derive(Serialize)]
+struct RecordedEventRef<'a, T> {
+    time: Duration,
+    event: &'a Event<T>,
+}
+
+/// Writes delivered events as JSON lines, see
+/// [`EventLoop::set_recorder`](crate::event_loop::EventLoop::set_recorder).
+pub struct Recorder {
+    writer: Box<dyn Write>,
+    start: Instant,
+}
+
+impl Recorder {
+    pub fn create(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self::new(BufWriter::new(File::create(path)?)))
+    }
+
+    pub fn new(writer: impl Write + 'static) -> Self {
+        Self {
+            writer: Box::new(writer),
+            start: Instant::now(),
+        }
+    }
+
+    pub fn record<T: Serialize>(&mut self, event: &Event<T>) -> Result<()> {
+        let recorded = RecordedEventRef {
+            time: self.start.elapsed(),
+            event,
+        };
+        serde_json::to_writer(&mut self.writer, &recorded)?;
+        self.writer.write_all(b"\n")?;
+        Ok(())
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        let _ = self.writer.flush();
+    }
+}
+
+/// Feeds a recording made by a [`Recorder`] back to an event handler.
+///
+/// Window ids are the ones of the recorded session, so handlers should only
+/// use them as keys.
+pub struct Replayer<T = ()> {
+    events: VecDeque<RecordedEvent<T>>,
+}
+
+impl<T: DeserializeOwned> Replayer<T> {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    pub fn from_reader(reader: impl BufRead) -> Result<Self> {
+        let mut events = VecDeque::new();
+        for line in reader.lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                events.push_back(serde_json::from_str(&line)?);
+            }
+        }
+        Ok(Self { events })
+    }
+}
+
+impl<T> Replayer<T> {
+    pub fn events(&self) -> &VecDeque<RecordedEvent<T>> {
+        &self.events
+    }
+
+    /// Delivers every event in recorded order, as fast as possible.
+    pub fn replay(self, mut event_handler: impl FnMut(Event<T>)) {
+        for recorded in self.events {
+            event_handler(recorded.event);
+        }
+    }
+
+    /// Delivers every event in recorded order, sleeping in between to
+    /// reproduce the original timing.
+    pub fn replay_timed(self, mut event_handler: impl FnMut(Event<T>)) {
+        let start = Instant::now();
+        for recorded in self.events {
+            if let Some(delay) = recorded.time.checked_sub(start.elapsed()) {
+                thread::sleep(delay);
+            }
+            event_handler(recorded.event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    /// Removes the file when dropped, even if the test failed.
+    struct TempFile(PathBuf);
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn replays_recorded_events_in_order() {
+        let file = TempFile(
+            std::env::temp_dir().join(format!("paneless_record_{}.jsonl", std::process::id())),
+        );
+        let path = &file.0;
+        {
+            let mut recorder = Recorder::create(path).unwrap();
+            recorder.record(&Event::<u32>::UserEvent(7)).unwrap();
+            recorder.record(&Event::<u32>::HandleSignaled(1)).unwrap();
+        }
+
+        let mut replayed = Vec::new();
+        Replayer::<u32>::open(path)
+            .unwrap()
+            .replay(|event| replayed.push(event));
+        assert_eq!(
+            replayed,
+            vec![Event::UserEvent(7), Event::HandleSignaled(1)]
+        );
+    }
+}
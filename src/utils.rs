@@ -1 +1,2 @@
 pub mod strings;
+pub mod trace;
@@ -0,0 +1,119 @@
+use windows::Win32::UI::WindowsAndMessaging::*;
+
+/// Reports an error that can't be returned to the caller, through `tracing`
+/// when the feature is enabled and stdout otherwise.
+macro_rules! log_error {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "tracing")]
+        tracing::error!($($arg)*);
+        #[cfg(not(feature = "tracing"))]
+        println!($($arg)*);
+    }};
+}
+pub(crate) use log_error;
+
+/// Opens a span around the handling of one window message.
+#[cfg(feature = "tracing")]
+pub(crate) fn message_span(
+    hwnd: windows::Win32::Foundation::HWND,
+    msg: u32,
+) -> tracing::span::EnteredSpan {
+    tracing::trace_span!(
+        "message",
+        hwnd = hwnd.0 as isize,
+        msg,
+        name = message_name(msg).unwrap_or("unknown"),
+    )
+    .entered()
+}
+
+/// Name of a standard window message, for logging.
+pub fn message_name(msg: u32) -> Option<&'static str> {
+    let name = match msg {
+        WM_NULL => "WM_NULL",
+        WM_CREATE => "WM_CREATE",
+        WM_DESTROY => "WM_DESTROY",
+        WM_MOVE => "WM_MOVE",
+        WM_SIZE => "WM_SIZE",
+        WM_ACTIVATE => "WM_ACTIVATE",
+        WM_SETFOCUS => "WM_SETFOCUS",
+        WM_KILLFOCUS => "WM_KILLFOCUS",
+        WM_ENABLE => "WM_ENABLE",
+        WM_SETTEXT => "WM_SETTEXT",
+        WM_GETTEXT => "WM_GETTEXT",
+        WM_PAINT => "WM_PAINT",
+        WM_CLOSE => "WM_CLOSE",
+        WM_QUIT => "WM_QUIT",
+        WM_ERASEBKGND => "WM_ERASEBKGND",
+        WM_SHOWWINDOW => "WM_SHOWWINDOW",
+        WM_ACTIVATEAPP => "WM_ACTIVATEAPP",
+        WM_SETCURSOR => "WM_SETCURSOR",
+        WM_MOUSEACTIVATE => "WM_MOUSEACTIVATE",
+        WM_GETMINMAXINFO => "WM_GETMINMAXINFO",
+        WM_WINDOWPOSCHANGING => "WM_WINDOWPOSCHANGING",
+        WM_WINDOWPOSCHANGED => "WM_WINDOWPOSCHANGED",
+        WM_COPYDATA => "WM_COPYDATA",
+        WM_GETICON => "WM_GETICON",
+        WM_SETICON => "WM_SETICON",
+        WM_NCCREATE => "WM_NCCREATE",
+        WM_NCDESTROY => "WM_NCDESTROY",
+        WM_NCCALCSIZE => "WM_NCCALCSIZE",
+        WM_NCHITTEST => "WM_NCHITTEST",
+        WM_NCPAINT => "WM_NCPAINT",
+        WM_NCACTIVATE => "WM_NCACTIVATE",
+        WM_NCMOUSEMOVE => "WM_NCMOUSEMOVE",
+        WM_NCLBUTTONDOWN => "WM_NCLBUTTONDOWN",
+        WM_NCMOUSELEAVE => "WM_NCMOUSELEAVE",
+        WM_KEYDOWN => "WM_KEYDOWN",
+        WM_KEYUP => "WM_KEYUP",
+        WM_CHAR => "WM_CHAR",
+        WM_DEADCHAR => "WM_DEADCHAR",
+        WM_SYSKEYDOWN => "WM_SYSKEYDOWN",
+        WM_SYSKEYUP => "WM_SYSKEYUP",
+        WM_SYSCHAR => "WM_SYSCHAR",
+        WM_IME_STARTCOMPOSITION => "WM_IME_STARTCOMPOSITION",
+        WM_IME_ENDCOMPOSITION => "WM_IME_ENDCOMPOSITION",
+        WM_IME_COMPOSITION => "WM_IME_COMPOSITION",
+        WM_SYSCOMMAND => "WM_SYSCOMMAND",
+        WM_TIMER => "WM_TIMER",
+        WM_MOUSEMOVE => "WM_MOUSEMOVE",
+        WM_LBUTTONDOWN => "WM_LBUTTONDOWN",
+        WM_LBUTTONUP => "WM_LBUTTONUP",
+        WM_LBUTTONDBLCLK => "WM_LBUTTONDBLCLK",
+        WM_RBUTTONDOWN => "WM_RBUTTONDOWN",
+        WM_RBUTTONUP => "WM_RBUTTONUP",
+        WM_RBUTTONDBLCLK => "WM_RBUTTONDBLCLK",
+        WM_MBUTTONDOWN => "WM_MBUTTONDOWN",
+        WM_MBUTTONUP => "WM_MBUTTONUP",
+        WM_MBUTTONDBLCLK => "WM_MBUTTONDBLCLK",
+        WM_MOUSEWHEEL => "WM_MOUSEWHEEL",
+        WM_XBUTTONDOWN => "WM_XBUTTONDOWN",
+        WM_XBUTTONUP => "WM_XBUTTONUP",
+        WM_MOUSEHWHEEL => "WM_MOUSEHWHEEL",
+        WM_CAPTURECHANGED => "WM_CAPTURECHANGED",
+        WM_ENTERSIZEMOVE => "WM_ENTERSIZEMOVE",
+        WM_EXITSIZEMOVE => "WM_EXITSIZEMOVE",
+        WM_DPICHANGED => "WM_DPICHANGED",
+        WM_INPUT => "WM_INPUT",
+        WM_TOUCH => "WM_TOUCH",
+        WM_DISPLAYCHANGE => "WM_DISPLAYCHANGE",
+        WM_SETTINGCHANGE => "WM_SETTINGCHANGE",
+        WM_HOTKEY => "WM_HOTKEY",
+        WM_USER..=0x7fff => "WM_USER",
+        WM_APP..=0xbfff => "WM_APP",
+        _ => return None,
+    };
+    Some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_window_messages() {
+        assert_eq!(message_name(WM_PAINT), Some("WM_PAINT"));
+        assert_eq!(message_name(WM_USER + 5), Some("WM_USER"));
+        assert_eq!(message_name(0xc123), None);
+    }
+}
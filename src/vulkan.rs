@@ -0,0 +1,33 @@
+use std::ffi::CStr;
+
+use ash::{khr::win32_surface, prelude::VkResult, vk, Entry, Instance};
+use windows::Win32::UI::WindowsAndMessaging::{GetWindowLongPtrW, IsWindow, GWLP_HINSTANCE};
+
+use crate::window::WindowsWindow;
+
+/// Instance extensions [`create_surface`] needs, to enable when creating the
+/// instance.
+pub fn required_instance_extensions() -> [&'static CStr; 2] {
+    [ash::khr::surface::NAME, win32_surface::NAME]
+}
+
+/// Creates a surface presenting to `window`. Destroy it with
+/// `ash::khr::surface::Instance::destroy_surface` before the window.
+///
+/// # Safety
+///
+/// `instance` must have been created from `entry` with the
+/// [`required_instance_extensions`] enabled.
+pub unsafe fn create_surface(
+    entry: &Entry,
+    instance: &Instance,
+    window: WindowsWindow,
+) -> VkResult<vk::SurfaceKHR> {
+    if !IsWindow(window.hwnd()).as_bool() {
+        return Err(vk::Result::ERROR_SURFACE_LOST_KHR);
+    }
+    let info = vk::Win32SurfaceCreateInfoKHR::default()
+        .hinstance(GetWindowLongPtrW(window.hwnd(), GWLP_HINSTANCE) as vk::HINSTANCE)
+        .hwnd(window.hwnd().0 as vk::HWND);
+    win32_surface::Instance::new(entry, instance).create_win32_surface(&info, None)
+}
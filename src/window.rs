@@ -1,3 +1,34 @@
-pub mod windows;
-
-pub use windows::WindowsWindow;
+pub mod accessibility;
+pub mod builder;
+pub mod class;
+pub mod cursor;
+pub mod dwm;
+pub mod gesture;
+#[cfg(feature = "raw-window-handle")]
+mod handle;
+pub mod icon;
+pub mod ime;
+pub mod monitor;
+pub mod painter;
+pub mod placement;
+pub mod pointer;
+pub mod raw_input;
+pub mod surface;
+pub mod windows;
+
+pub use builder::WindowBuilder;
+pub use class::WindowClass;
+pub use cursor::{
+    cursor_position, set_cursor_position, Cursor, CursorGrabMode, CursorIcon, CustomCursor,
+};
+pub use dwm::{system_theme, Backdrop, CornerPreference, Rgb, TitlebarColors};
+pub use icon::Icon;
+pub use monitor::MonitorHandle;
+pub use painter::{Brush, Painter, Pen};
+pub use placement::WindowPlacement;
+pub use raw_input::RawInputMode;
+pub use surface::Surface;
+pub use windows::{
+    Animation, Fullscreen, HitTest, HitTestFn, MsgHook, ResizeDirection, SlideDirection,
+    UserAttentionType, WindowId, WindowsWindow,
+};
@@ -0,0 +1,166 @@
+use std::{ffi::c_void, mem, sync::Mutex};
+
+use anyhow::{bail, Result};
+use windows::Win32::UI::{
+    Accessibility::{
+        FILTERKEYS, SKF_CONFIRMHOTKEY, SKF_HOTKEYACTIVE, SKF_STICKYKEYSON, STICKYKEYS,
+        STICKYKEYS_FLAGS, TOGGLEKEYS,
+    },
+    Input::KeyboardAndMouse::GetFocus,
+    WindowsAndMessaging::{
+        SystemParametersInfoW, FKF_CONFIRMHOTKEY, FKF_FILTERKEYSON, FKF_HOTKEYACTIVE,
+        SPI_GETFILTERKEYS, SPI_GETSTICKYKEYS, SPI_GETTOGGLEKEYS, SPI_SETFILTERKEYS,
+        SPI_SETSTICKYKEYS, SPI_SETTOGGLEKEYS, SYSTEM_PARAMETERS_INFO_ACTION,
+        SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS, TKF_CONFIRMHOTKEY, TKF_HOTKEYACTIVE, TKF_TOGGLEKEYSON,
+    },
+};
+
+use super::{windows::window_state, WindowsWindow};
+
+/// The settings found before suppressing their shortcuts, with the window
+/// that did. The settings are system-wide, so one window at most holds them.
+static SUPPRESSED: Mutex<Option<(isize, Shortcuts)>> = Mutex::new(None);
+
+#[derive(Clone, Copy)]
+struct Shortcuts {
+    sticky_keys: STICKYKEYS,
+    toggle_keys: TOGGLEKEYS,
+    filter_keys: FILTERKEYS,
+}
+
+/// Accessibility shortcuts getting in the way of games: holding Shift or
+/// pressing it five times opens the Sticky Keys prompt, and likewise for
+/// Filter Keys (right Shift held) and Toggle Keys (NumLock held).
+impl WindowsWindow {
+    /// Turns the Sticky, Filter and Toggle Keys shortcuts off while the
+    /// window is fullscreen and focused, and restores them afterwards.
+    /// Features the user turned on stay untouched, only their shortcuts go.
+    ///
+    /// The shortcuts stay off if the process exits without the window
+    /// losing the focus or being destroyed first.
+    pub fn set_suppress_accessibility_shortcuts(&self, suppress: bool) -> Result<()> {
+        let Some(state) = (unsafe { window_state(self.hwnd()) }) else {
+            bail!("Not a paneless window");
+        };
+        state.suppress_accessibility_shortcuts.set(suppress);
+        self.update_accessibility_shortcuts(unsafe { GetFocus() } == self.hwnd());
+        Ok(())
+    }
+
+    /// Suppresses or restores the shortcuts after the window gained or lost
+    /// the focus, or entered or left fullscreen.
+    pub(crate) fn update_accessibility_shortcuts(&self, focused: bool) {
+        let suppress = focused
+            && self.fullscreen().is_some()
+            && unsafe { window_state(self.hwnd()) }
+                .is_some_and(|state| state.suppress_accessibility_shortcuts.get());
+        let mut suppressed = SUPPRESSED.lock().unwrap_or_else(|e| e.into_inner());
+        let owner = self.hwnd().0 as isize;
+        match *suppressed {
+            None if suppress => {
+                let Some(shortcuts) = Shortcuts::current() else {
+                    return;
+                };
+                shortcuts.without_hotkeys().apply();
+                *suppressed = Some((owner, shortcuts));
+            }
+            Some((hwnd, shortcuts)) if hwnd == owner && !suppress => {
+                shortcuts.apply();
+                *suppressed = None;
+            }
+            _ => (),
+        }
+    }
+}
+
+impl Shortcuts {
+    fn current() -> Option<Self> {
+        let mut shortcuts = Self {
+            sticky_keys: STICKYKEYS {
+                cbSize: mem::size_of::<STICKYKEYS>() as u32,
+                ..Default::default()
+            },
+            toggle_keys: TOGGLEKEYS {
+                cbSize: mem::size_of::<TOGGLEKEYS>() as u32,
+                ..Default::default()
+            },
+            filter_keys: FILTERKEYS {
+                cbSize: mem::size_of::<FILTERKEYS>() as u32,
+                ..Default::default()
+            },
+        };
+        unsafe {
+            parameter(SPI_GETSTICKYKEYS, &mut shortcuts.sticky_keys).ok()?;
+            parameter(SPI_GETTOGGLEKEYS, &mut shortcuts.toggle_keys).ok()?;
+            parameter(SPI_GETFILTERKEYS, &mut shortcuts.filter_keys).ok()?;
+        }
+        Some(shortcuts)
+    }
+
+    fn without_hotkeys(mut self) -> Self {
+        self.sticky_keys.dwFlags = STICKYKEYS_FLAGS(without_hotkey(
+            self.sticky_keys.dwFlags.0,
+            SKF_STICKYKEYSON.0,
+            SKF_HOTKEYACTIVE.0 | SKF_CONFIRMHOTKEY.0,
+        ));
+        self.toggle_keys.dwFlags = without_hotkey(
+            self.toggle_keys.dwFlags,
+            TKF_TOGGLEKEYSON,
+            TKF_HOTKEYACTIVE | TKF_CONFIRMHOTKEY,
+        );
+        self.filter_keys.dwFlags = without_hotkey(
+            self.filter_keys.dwFlags,
+            FKF_FILTERKEYSON,
+            FKF_HOTKEYACTIVE | FKF_CONFIRMHOTKEY,
+        );
+        self
+    }
+
+    fn apply(mut self) {
+        // Not persisted to the user profile
+        unsafe {
+            let _ = parameter(SPI_SETSTICKYKEYS, &mut self.sticky_keys);
+            let _ = parameter(SPI_SETTOGGLEKEYS, &mut self.toggle_keys);
+            let _ = parameter(SPI_SETFILTERKEYS, &mut self.filter_keys);
+        }
+    }
+}
+
+/// Gets or sets a parameter structure, which starts with its size.
+unsafe fn parameter<T>(action: SYSTEM_PARAMETERS_INFO_ACTION, value: &mut T) -> Result<()> {
+    SystemParametersInfoW(
+        action,
+        mem::size_of::<T>() as u32,
+        Some(value as *mut T as *mut c_void),
+        SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+    )?;
+    Ok(())
+}
+
+/// Clears the shortcut flags of a feature, unless the feature is on and its
+/// shortcut is how the user turns it back off.
+fn without_hotkey(flags: u32, on: u32, hotkey: u32) -> u32 {
+    if flags & on != 0 {
+        flags
+    } else {
+        flags & !hotkey
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clears_shortcuts_of_features_off() {
+        let hotkey = SKF_HOTKEYACTIVE.0 | SKF_CONFIRMHOTKEY.0;
+        assert_eq!(
+            without_hotkey(hotkey | 0x40, SKF_STICKYKEYSON.0, hotkey),
+            0x40
+        );
+        assert_eq!(
+            without_hotkey(hotkey | SKF_STICKYKEYSON.0, SKF_STICKYKEYSON.0, hotkey),
+            hotkey | SKF_STICKYKEYSON.0
+        );
+    }
+}
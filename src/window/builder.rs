@@ -0,0 +1,306 @@
+use anyhow::{bail, Result};
+use windows::Win32::{
+    Foundation::HWND,
+    UI::WindowsAndMessaging::{
+        IsWindow, WINDOW_EX_STYLE, WINDOW_STYLE, WS_CHILD, WS_CLIPSIBLINGS,
+        WS_EX_NOREDIRECTIONBITMAP, WS_EX_TOOLWINDOW, WS_EX_TOPMOST, WS_MAXIMIZE, WS_MAXIMIZEBOX,
+        WS_OVERLAPPEDWINDOW, WS_POPUP, WS_THICKFRAME,
+    },
+};
+
+use super::{Icon, WindowClass, WindowsWindow};
+use crate::event::Theme;
+
+/// Attributes of a window, checked and applied by [`WindowBuilder::build`].
+#[derive(Debug, Clone)]
+pub struct WindowBuilder {
+    pub(crate) title: String,
+    pub(crate) size: Option<(u32, u32)>,
+    pub(crate) position: Option<(i32, i32)>,
+    pub(crate) centered: bool,
+    pub(crate) resizable: bool,
+    pub(crate) decorations: bool,
+    pub(crate) visible: bool,
+    pub(crate) maximized: bool,
+    pub(crate) transparent: bool,
+    pub(crate) always_on_top: bool,
+    pub(crate) skip_taskbar: bool,
+    pub(crate) no_redirection_bitmap: bool,
+    pub(crate) parent: Option<HWND>,
+    pub(crate) fill_parent: bool,
+    pub(crate) owner: Option<HWND>,
+    pub(crate) icon: Option<Icon>,
+    pub(crate) class: WindowClass,
+    pub(crate) theme: Option<Theme>,
+}
+
+impl Default for WindowBuilder {
+    fn default() -> Self {
+        Self {
+            title: String::new(),
+            size: None,
+            position: None,
+            centered: false,
+            resizable: true,
+            decorations: true,
+            visible: true,
+            maximized: false,
+            transparent: false,
+            always_on_top: false,
+            skip_taskbar: false,
+            no_redirection_bitmap: false,
+            parent: None,
+            fill_parent: false,
+            owner: None,
+            icon: None,
+            class: WindowClass::default(),
+            theme: None,
+        }
+    }
+}
+
+impl WindowBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Size of the client area, the system picks one by default.
+    pub fn with_size(mut self, width: u32, height: u32) -> Self {
+        self.size = Some((width, height));
+        self
+    }
+
+    /// Position of the outer top-left corner, in screen coordinates (or
+    /// relative to the parent's client area for child windows).
+    pub fn with_position(mut self, x: i32, y: i32) -> Self {
+        self.position = Some((x, y));
+        self
+    }
+
+    /// Centers the window on the work area of its monitor, taking precedence
+    /// over the system's default placement.
+    pub fn with_centered(mut self, centered: bool) -> Self {
+        self.centered = centered;
+        self
+    }
+
+    pub fn with_resizable(mut self, resizable: bool) -> Self {
+        self.resizable = resizable;
+        self
+    }
+
+    /// Whether the window has a title bar and borders.
+    pub fn with_decorations(mut self, decorations: bool) -> Self {
+        self.decorations = decorations;
+        self
+    }
+
+    pub fn with_visible(mut self, visible: bool) -> Self {
+        self.visible = visible;
+        self
+    }
+
+    pub fn with_maximized(mut self, maximized: bool) -> Self {
+        self.maximized = maximized;
+        self
+    }
+
+    /// Gives the client area per-pixel alpha: the compositor blends it over
+    /// what is behind the window instead of filling it with the background
+    /// color, which is never erased. Pixels must be drawn with premultiplied
+    /// alpha (Direct2D, Direct3D, ...), plain GDI leaves them transparent.
+    pub fn with_transparent(mut self, transparent: bool) -> Self {
+        self.transparent = transparent;
+        self
+    }
+
+    pub fn with_always_on_top(mut self, always_on_top: bool) -> Self {
+        self.always_on_top = always_on_top;
+        self
+    }
+
+    /// Makes a tool window: no taskbar button and no Alt+Tab entry, with a
+    /// smaller title bar. For palettes and tray-only applications.
+    pub fn with_skip_taskbar(mut self, skip_taskbar: bool) -> Self {
+        self.skip_taskbar = skip_taskbar;
+        self
+    }
+
+    /// Skips the surface GDI draws into before the compositor picks it up.
+    /// Saves memory and a copy for windows presented only through
+    /// DirectComposition or a flip model swap chain, but GDI painting and
+    /// the class background no longer show up.
+    pub fn with_no_redirection_bitmap(mut self, no_redirection_bitmap: bool) -> Self {
+        self.no_redirection_bitmap = no_redirection_bitmap;
+        self
+    }
+
+    /// Creates the window as a child of `parent`, e.g. a render pane
+    /// embedded in a host window, clipped to its client area. Positions are
+    /// then relative to the parent's client area.
+    pub fn with_parent(mut self, parent: HWND) -> Self {
+        self.parent = Some(parent);
+        self
+    }
+
+    /// Keeps a child window covering the whole client area of its parent as
+    /// the parent is resized.
+    pub fn with_fill_parent(mut self, fill_parent: bool) -> Self {
+        self.fill_parent = fill_parent;
+        self
+    }
+
+    /// Makes `owner` own the window: it stays above its owner, is minimized
+    /// and destroyed with it and has no taskbar button of its own. Dialogs
+    /// and tool palettes are owned windows, see also
+    /// [`WindowsWindow::show_modal`].
+    pub fn with_owner(mut self, owner: HWND) -> Self {
+        self.owner = Some(owner);
+        self
+    }
+
+    /// Icon of the title bar and taskbar button.
+    pub fn with_icon(mut self, icon: Icon) -> Self {
+        self.icon = Some(icon);
+        self
+    }
+
+    /// Window class to create the window with, see [`WindowClass`].
+    pub fn with_class(mut self, class: WindowClass) -> Self {
+        self.class = class;
+        self
+    }
+
+    /// Theme of the title bar, `None` follows the system setting.
+    pub fn with_theme(mut self, theme: Option<Theme>) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Checks the attributes and creates the window on the calling thread,
+    /// whose event loop will then deliver its events.
+    pub fn build(&self) -> Result<WindowsWindow> {
+        self.validate()?;
+        WindowsWindow::create(self)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if let Some((width, height)) = self.size {
+            if width == 0 || height == 0 || width > i32::MAX as u32 || height > i32::MAX as u32 {
+                bail!("Invalid window size {}x{}", width, height);
+            }
+        }
+        // CW_USEDEFAULT only sizes overlapped windows
+        if self.size.is_none() && !self.fill_parent && (!self.decorations || self.parent.is_some())
+        {
+            bail!("Undecorated and child windows need an explicit size");
+        }
+        if let Some(owner) = self.owner {
+            if self.parent.is_some() {
+                bail!("A window can't have both a parent and an owner");
+            }
+            if !unsafe { IsWindow(owner) }.as_bool() {
+                bail!("The owner window {:?} doesn't exist", owner);
+            }
+        }
+        if self.fill_parent && self.parent.is_none() {
+            bail!("Only child windows can fill their parent");
+        }
+        if let Some(parent) = self.parent {
+            if !unsafe { IsWindow(parent) }.as_bool() {
+                bail!("The parent window {:?} doesn't exist", parent);
+            }
+            if self.maximized {
+                bail!("Child windows can't start maximized");
+            }
+            if self.centered {
+                bail!("Child windows can't be centered on a monitor");
+            }
+        }
+        if self.centered && self.position.is_some() {
+            bail!("A window can't be both centered and explicitly positioned");
+        }
+        if self.class.name.is_empty() || self.class.name.contains('\0') {
+            bail!("Invalid window class name {:?}", self.class.name);
+        }
+        if self.title.contains('\0') {
+            bail!("The window title can't contain NUL characters");
+        }
+        Ok(())
+    }
+
+    pub(crate) fn styles(&self) -> (WINDOW_STYLE, WINDOW_EX_STYLE) {
+        let mut style = WINDOW_STYLE::default();
+        if self.parent.is_some() {
+            style |= WS_CHILD | WS_CLIPSIBLINGS;
+        }
+        if self.maximized {
+            style |= WS_MAXIMIZE;
+        }
+        let style = frame_style(style, self.decorations, self.resizable);
+
+        let mut ex_style = WINDOW_EX_STYLE::default();
+        if self.always_on_top {
+            ex_style |= WS_EX_TOPMOST;
+        }
+        if self.skip_taskbar {
+            ex_style |= WS_EX_TOOLWINDOW;
+        }
+        if self.no_redirection_bitmap {
+            ex_style |= WS_EX_NOREDIRECTIONBITMAP;
+        }
+        (style, ex_style)
+    }
+}
+
+/// Sets the frame bits of `style` for the given attributes, keeping the
+/// others. Undecorated windows have no resize border either.
+pub(crate) fn frame_style(style: WINDOW_STYLE, decorations: bool, resizable: bool) -> WINDOW_STYLE {
+    let mut style = style & !(WS_OVERLAPPEDWINDOW | WS_POPUP);
+    if decorations {
+        style |= WS_OVERLAPPEDWINDOW;
+        if !resizable {
+            style &= !(WS_THICKFRAME | WS_MAXIMIZEBOX);
+        }
+    } else if !style.contains(WS_CHILD) {
+        style |= WS_POPUP;
+    }
+    style
+}
+
+#[cfg(test)]
+mod tests {
+    use windows::Win32::UI::WindowsAndMessaging::WS_CAPTION;
+
+    use super::*;
+
+    #[test]
+    fn derives_styles_from_attributes() {
+        let (style, ex_style) = WindowBuilder::new().styles();
+        assert_eq!(style, WS_OVERLAPPEDWINDOW);
+        assert_eq!(ex_style, WINDOW_EX_STYLE::default());
+
+        let (style, ex_style) = WindowBuilder::new()
+            .with_resizable(false)
+            .with_always_on_top(true)
+            .with_no_redirection_bitmap(true)
+            .styles();
+        assert!(style.contains(WS_CAPTION));
+        assert!(!style.contains(WS_THICKFRAME));
+        assert!(ex_style.contains(WS_EX_TOPMOST | WS_EX_NOREDIRECTIONBITMAP));
+
+        let (style, _) = WindowBuilder::new().with_decorations(false).styles();
+        assert_eq!(style, WS_POPUP);
+    }
+
+    #[test]
+    fn rejects_empty_sizes() {
+        assert!(WindowBuilder::new().with_size(0, 600).validate().is_err());
+        assert!(WindowBuilder::new().with_size(800, 600).validate().is_ok());
+    }
+}
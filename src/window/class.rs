@@ -0,0 +1,65 @@
+use windows::Win32::{
+    Graphics::Gdi::{COLOR_WINDOW, HBRUSH},
+    UI::WindowsAndMessaging::{CS_DBLCLKS, CS_HREDRAW, CS_VREDRAW, WNDCLASS_STYLES},
+};
+
+use super::{windows::sys_color_brush, Icon};
+
+/// Attributes of the Win32 window class a window is created with, see
+/// [`WindowBuilder::with_class`](super::WindowBuilder::with_class).
+///
+/// A class is registered once per process under its name, later windows
+/// using the same name must use the same style and background. Their icon
+/// is ignored, the class keeps the first one.
+#[derive(Debug, Clone)]
+pub struct WindowClass {
+    pub(crate) name: String,
+    pub(crate) style: WNDCLASS_STYLES,
+    pub(crate) background: Option<HBRUSH>,
+    pub(crate) icon: Option<Icon>,
+}
+
+impl Default for WindowClass {
+    fn default() -> Self {
+        Self {
+            name: "paneless_window".to_owned(),
+            style: CS_HREDRAW | CS_VREDRAW | CS_DBLCLKS,
+            background: Some(sys_color_brush(COLOR_WINDOW)),
+            icon: None,
+        }
+    }
+}
+
+impl WindowClass {
+    /// A class named `name` with the default attributes.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Class styles, `CS_HREDRAW | CS_VREDRAW | CS_DBLCLKS` by default.
+    /// Without `CS_DBLCLKS` no [`WindowEvent::DoubleClick`] is delivered.
+    ///
+    /// [`WindowEvent::DoubleClick`]: crate::event::WindowEvent::DoubleClick
+    pub fn with_style(mut self, style: WNDCLASS_STYLES) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Brush erasing the background before every paint, `None` leaves it to
+    /// the application.
+    pub fn with_background(mut self, background: Option<HBRUSH>) -> Self {
+        self.background = background;
+        self
+    }
+
+    /// Icon of the windows that don't set their own. The class keeps it for
+    /// the rest of the process, icons of later registrations under the same
+    /// name are ignored.
+    pub fn with_icon(mut self, icon: Icon) -> Self {
+        self.icon = Some(icon);
+        self
+    }
+}
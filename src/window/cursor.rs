@@ -0,0 +1,376 @@
+use std::{path::Path, rc::Rc};
+
+use anyhow::{bail, Result};
+use windows::{
+    core::PCWSTR,
+    Win32::{
+        Foundation::{LPARAM, POINT, RECT, WPARAM},
+        Graphics::Gdi::{CreateBitmap, DeleteObject},
+        UI::{
+            Input::KeyboardAndMouse::{GetFocus, TrackMouseEvent, TME_LEAVE, TRACKMOUSEEVENT},
+            WindowsAndMessaging::{
+                ClipCursor, CreateIconIndirect, DestroyCursor, GetCursorPos, LoadCursorFromFileW,
+                SendMessageW, SetCursor, SetCursorPos, WindowFromPoint, HCURSOR, HTCLIENT,
+                ICONINFO, IDC_APPSTARTING, IDC_ARROW, IDC_CROSS, IDC_HAND, IDC_HELP, IDC_IBEAM,
+                IDC_NO, IDC_SIZEALL, IDC_SIZENESW, IDC_SIZENS, IDC_SIZENWSE, IDC_SIZEWE, IDC_WAIT,
+                WM_MOUSEMOVE, WM_SETCURSOR,
+            },
+        },
+    },
+};
+
+use super::{
+    icon::{and_mask, rgba_to_bgra},
+    windows::{load_default_cursor, window_state},
+    WindowsWindow,
+};
+use crate::utils::strings::str_to_wstr;
+
+/// How the cursor is held by a window, see [`WindowsWindow::set_cursor_grab`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CursorGrabMode {
+    /// The cursor moves freely.
+    #[default]
+    None,
+    /// The cursor can't leave the client area, e.g. for strategy games
+    /// scrolling at the screen edges.
+    Confined,
+    /// The cursor is hidden and held at the center of the client area, for
+    /// mouse look. Movement is only reported by
+    /// [`WindowsWindow::set_raw_mouse`].
+    Locked,
+}
+
+/// A system cursor, see [`WindowsWindow::set_cursor`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CursorIcon {
+    #[default]
+    Arrow,
+    /// A pointing hand, for links.
+    Hand,
+    /// The text selection caret.
+    IBeam,
+    Crosshair,
+    /// Busy, the application doesn't take input.
+    Wait,
+    /// Busy in the background, the arrow with a spinner.
+    Progress,
+    /// The arrow with a question mark.
+    Help,
+    NotAllowed,
+    /// Four-way arrow for moving things around.
+    Move,
+    /// Vertical double arrow.
+    ResizeNS,
+    /// Horizontal double arrow.
+    ResizeEW,
+    /// Double arrow from the bottom left to the top right.
+    ResizeNESW,
+    /// Double arrow from the top left to the bottom right.
+    ResizeNWSE,
+}
+
+impl CursorIcon {
+    fn resource(self) -> PCWSTR {
+        match self {
+            CursorIcon::Arrow => IDC_ARROW,
+            CursorIcon::Hand => IDC_HAND,
+            CursorIcon::IBeam => IDC_IBEAM,
+            CursorIcon::Crosshair => IDC_CROSS,
+            CursorIcon::Wait => IDC_WAIT,
+            CursorIcon::Progress => IDC_APPSTARTING,
+            CursorIcon::Help => IDC_HELP,
+            CursorIcon::NotAllowed => IDC_NO,
+            CursorIcon::Move => IDC_SIZEALL,
+            CursorIcon::ResizeNS => IDC_SIZENS,
+            CursorIcon::ResizeEW => IDC_SIZEWE,
+            CursorIcon::ResizeNESW => IDC_SIZENESW,
+            CursorIcon::ResizeNWSE => IDC_SIZENWSE,
+        }
+    }
+}
+
+/// A cursor made by the application. Cheap to clone, the cursor is destroyed
+/// with the last clone, and windows keep the clone they show alive.
+#[derive(Debug, Clone)]
+pub struct CustomCursor {
+    inner: Rc<OwnedCursor>,
+}
+
+#[derive(Debug)]
+struct OwnedCursor(HCURSOR);
+
+impl Drop for OwnedCursor {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = DestroyCursor(self.0);
+        }
+    }
+}
+
+impl CustomCursor {
+    /// Creates a cursor from `width` by `height` pixels of straight RGBA,
+    /// pointing at the pixel `hotspot`.
+    pub fn from_rgba(rgba: &[u8], width: u32, height: u32, hotspot: (u32, u32)) -> Result<Self> {
+        let bgra = rgba_to_bgra(rgba, width, height)?;
+        if hotspot.0 >= width || hotspot.1 >= height {
+            bail!(
+                "Hotspot {:?} is outside of the {}x{} cursor",
+                hotspot,
+                width,
+                height
+            );
+        }
+        let and_mask = and_mask(rgba, width, height);
+        unsafe {
+            let color = CreateBitmap(
+                width as i32,
+                height as i32,
+                1,
+                32,
+                Some(bgra.as_ptr().cast()),
+            );
+            let mask = CreateBitmap(
+                width as i32,
+                height as i32,
+                1,
+                1,
+                Some(and_mask.as_ptr().cast()),
+            );
+            let info = ICONINFO {
+                fIcon: false.into(),
+                xHotspot: hotspot.0,
+                yHotspot: hotspot.1,
+                hbmMask: mask,
+                hbmColor: color,
+            };
+            let cursor = CreateIconIndirect(&info);
+            // The cursor has its own copies
+            let _ = DeleteObject(color);
+            let _ = DeleteObject(mask);
+            Ok(Self::from_hcursor(HCURSOR(cursor?.0)))
+        }
+    }
+
+    /// Loads a `.cur` file, or an animated `.ani` one.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        let path = str_to_wstr(&path.as_ref().to_string_lossy());
+        let hcursor = unsafe { LoadCursorFromFileW(PCWSTR(path.as_ptr()))? };
+        Ok(Self::from_hcursor(hcursor))
+    }
+
+    fn from_hcursor(hcursor: HCURSOR) -> Self {
+        Self {
+            inner: Rc::new(OwnedCursor(hcursor)),
+        }
+    }
+
+    pub fn hcursor(&self) -> HCURSOR {
+        self.inner.0
+    }
+}
+
+/// What a window shows as its cursor, see [`WindowsWindow::set_cursor`].
+#[derive(Debug, Clone)]
+pub enum Cursor {
+    Icon(CursorIcon),
+    Custom(CustomCursor),
+}
+
+impl Default for Cursor {
+    fn default() -> Self {
+        Cursor::Icon(CursorIcon::default())
+    }
+}
+
+impl From<CursorIcon> for Cursor {
+    fn from(icon: CursorIcon) -> Self {
+        Cursor::Icon(icon)
+    }
+}
+
+impl From<CustomCursor> for Cursor {
+    fn from(cursor: CustomCursor) -> Self {
+        Cursor::Custom(cursor)
+    }
+}
+
+/// Where the cursor is, in screen coordinates.
+pub fn cursor_position() -> Result<(i32, i32)> {
+    let mut cursor = POINT::default();
+    unsafe { GetCursorPos(&mut cursor)? };
+    Ok((cursor.x, cursor.y))
+}
+
+/// Moves the cursor to `x`, `y` in screen coordinates.
+pub fn set_cursor_position(x: i32, y: i32) -> Result<()> {
+    unsafe { SetCursorPos(x, y)? };
+    Ok(())
+}
+
+/// The mouse cursor while it is over a window.
+impl WindowsWindow {
+    /// Holds the cursor in the window while it has the focus. The grab is
+    /// released while the user switches to another window and taken again
+    /// when they come back.
+    pub fn set_cursor_grab(&self, mode: CursorGrabMode) -> Result<()> {
+        let Some(state) = (unsafe { window_state(self.hwnd()) }) else {
+            bail!("Not a paneless window");
+        };
+        let previous = state.cursor_grab.replace(mode);
+        if mode == CursorGrabMode::None && previous != CursorGrabMode::None {
+            unsafe { ClipCursor(None)? };
+        }
+        self.update_cursor_clip();
+        self.refresh_cursor();
+        Ok(())
+    }
+
+    pub fn cursor_grab(&self) -> CursorGrabMode {
+        unsafe { window_state(self.hwnd()) }
+            .map(|state| state.cursor_grab.get())
+            .unwrap_or_default()
+    }
+
+    /// Shows `cursor` while the cursor is over the client area, e.g. a hand
+    /// over links. Set it again on every move for cursors depending on what
+    /// is under them.
+    pub fn set_cursor(&self, cursor: impl Into<Cursor>) -> Result<()> {
+        let Some(state) = (unsafe { window_state(self.hwnd()) }) else {
+            bail!("Not a paneless window");
+        };
+        *state.cursor.borrow_mut() = cursor.into();
+        self.refresh_cursor();
+        Ok(())
+    }
+
+    /// Where the cursor is, in client coordinates. Outside of the client
+    /// area it is negative or beyond [`inner_size`](Self::inner_size).
+    pub fn cursor_position(&self) -> Result<(i32, i32)> {
+        let (x, y) = cursor_position()?;
+        Ok(self.screen_to_client(x, y))
+    }
+
+    /// Moves the cursor to `x`, `y` in client coordinates, e.g. to warp it
+    /// back after a drag.
+    pub fn set_cursor_position(&self, x: i32, y: i32) -> Result<()> {
+        let (x, y) = self.client_to_screen(x, y);
+        set_cursor_position(x, y)
+    }
+
+    /// Hides the cursor while it is over the client area, e.g. while typing
+    /// or playing a video. Unlike `ShowCursor`, this doesn't affect other
+    /// windows and doesn't need balanced calls.
+    pub fn set_cursor_visible(&self, visible: bool) -> Result<()> {
+        let Some(state) = (unsafe { window_state(self.hwnd()) }) else {
+            bail!("Not a paneless window");
+        };
+        state.cursor_hidden.set(!visible);
+        self.refresh_cursor();
+        Ok(())
+    }
+
+    /// Sets the cursor of the client area on WM_SETCURSOR, `false` leaves it
+    /// to the default handling.
+    pub(crate) fn set_client_cursor(&self) -> bool {
+        let Some(state) = (unsafe { window_state(self.hwnd()) }) else {
+            return false;
+        };
+        let cursor =
+            if state.cursor_hidden.get() || state.cursor_grab.get() == CursorGrabMode::Locked {
+                HCURSOR::default()
+            } else {
+                match &*state.cursor.borrow() {
+                    Cursor::Icon(icon) => match load_default_cursor(icon.resource()) {
+                        Ok(cursor) => cursor,
+                        Err(_) => return false,
+                    },
+                    Cursor::Custom(cursor) => cursor.hcursor(),
+                }
+            };
+        unsafe { SetCursor(cursor) };
+        true
+    }
+
+    /// Asks for a WM_MOUSELEAVE on the first move over the client area,
+    /// `true` if the cursor just entered it.
+    pub(crate) fn track_cursor(&self) -> bool {
+        let Some(state) = (unsafe { window_state(self.hwnd()) }) else {
+            return false;
+        };
+        if state.cursor_inside.get() {
+            return false;
+        }
+        let mut track = TRACKMOUSEEVENT {
+            cbSize: std::mem::size_of::<TRACKMOUSEEVENT>() as u32,
+            dwFlags: TME_LEAVE,
+            hwndTrack: self.hwnd(),
+            dwHoverTime: 0,
+        };
+        if unsafe { TrackMouseEvent(&mut track) }.is_err() {
+            return false;
+        }
+        state.cursor_inside.set(true);
+        true
+    }
+
+    /// Applies a cursor change right away if the cursor is over the client
+    /// area, instead of on its next move.
+    fn refresh_cursor(&self) {
+        let mut cursor = POINT::default();
+        if unsafe { GetCursorPos(&mut cursor) }.is_err()
+            || unsafe { WindowFromPoint(cursor) } != self.hwnd()
+        {
+            return;
+        }
+        let (x, y) = self.screen_to_client(cursor.x, cursor.y);
+        let Ok((width, height)) = self.inner_size() else {
+            return;
+        };
+        if (0..width as i32).contains(&x) && (0..height as i32).contains(&y) {
+            unsafe {
+                SendMessageW(
+                    self.hwnd(),
+                    WM_SETCURSOR,
+                    WPARAM(self.hwnd().0 as usize),
+                    LPARAM(((WM_MOUSEMOVE as isize) << 16) | HTCLIENT as isize),
+                );
+            }
+        }
+    }
+
+    /// Clips the cursor for the grab mode, after the window moved, resized or
+    /// gained or lost the focus.
+    pub(crate) fn update_cursor_clip(&self) {
+        let mode = self.cursor_grab();
+        if mode == CursorGrabMode::None {
+            return;
+        }
+        if unsafe { GetFocus() } != self.hwnd() {
+            let _ = unsafe { ClipCursor(None) };
+            return;
+        }
+        let Ok((width, height)) = self.inner_size() else {
+            return;
+        };
+        let (left, top) = self.client_to_screen(0, 0);
+        let clip = match mode {
+            CursorGrabMode::Locked => {
+                let (x, y) = (left + width as i32 / 2, top + height as i32 / 2);
+                RECT {
+                    left: x,
+                    top: y,
+                    right: x + 1,
+                    bottom: y + 1,
+                }
+            }
+            _ => RECT {
+                left,
+                top,
+                right: left + width as i32,
+                bottom: top + height as i32,
+            },
+        };
+        let _ = unsafe { ClipCursor(Some(&clip)) };
+    }
+}
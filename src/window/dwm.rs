@@ -0,0 +1,298 @@
+use std::{ffi::c_void, mem, sync::LazyLock};
+
+use anyhow::{bail, Result};
+use windows::{
+    core::{s, w},
+    Win32::{
+        Foundation::{BOOL, ERROR_SUCCESS, HWND},
+        Graphics::{
+            Dwm::{
+                DwmEnableBlurBehindWindow, DwmExtendFrameIntoClientArea, DwmSetWindowAttribute,
+                DWMNCRP_ENABLED, DWMNCRP_USEWINDOWSTYLE, DWMSBT_MAINWINDOW, DWMSBT_NONE,
+                DWMSBT_TABBEDWINDOW, DWMSBT_TRANSIENTWINDOW, DWMWA_BORDER_COLOR,
+                DWMWA_CAPTION_COLOR, DWMWA_COLOR_DEFAULT, DWMWA_NCRENDERING_POLICY,
+                DWMWA_SYSTEMBACKDROP_TYPE, DWMWA_TEXT_COLOR, DWMWA_USE_IMMERSIVE_DARK_MODE,
+                DWMWA_WINDOW_CORNER_PREFERENCE, DWMWCP_DEFAULT, DWMWCP_DONOTROUND, DWMWCP_ROUND,
+                DWMWCP_ROUNDSMALL, DWMWINDOWATTRIBUTE, DWM_BB_ENABLE, DWM_BLURBEHIND,
+            },
+            Gdi::HRGN,
+        },
+        System::{
+            LibraryLoader::{GetModuleHandleW, GetProcAddress},
+            Registry::{RegGetValueW, HKEY_CURRENT_USER, RRF_RT_REG_DWORD},
+        },
+        UI::Controls::MARGINS,
+    },
+};
+
+use super::{windows::window_state, WindowsWindow};
+use crate::event::{Theme, WindowEvent};
+
+/// The light or dark mode the user picked for applications, light if it
+/// can't be read.
+pub fn system_theme() -> Theme {
+    let mut apps_use_light_theme = 1u32;
+    let mut size = mem::size_of::<u32>() as u32;
+    let result = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            w!("Software\\Microsoft\\Windows\\CurrentVersion\\Themes\\Personalize"),
+            w!("AppsUseLightTheme"),
+            RRF_RT_REG_DWORD,
+            None,
+            Some(&mut apps_use_light_theme as *mut u32 as *mut c_void),
+            Some(&mut size),
+        )
+    };
+    if result == ERROR_SUCCESS && apps_use_light_theme == 0 {
+        Theme::Dark
+    } else {
+        Theme::Light
+    }
+}
+
+/// How Windows 11 rounds the corners of a window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CornerPreference {
+    /// Let the system decide, which doesn't round undecorated windows.
+    #[default]
+    Default,
+    DoNotRound,
+    Round,
+    /// Rounds with a smaller radius, as used by menus and tooltips.
+    RoundSmall,
+}
+
+/// An opaque color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    /// `0x00BBGGRR`, as GDI and DWM expect it.
+    pub(super) fn colorref(self) -> u32 {
+        u32::from_le_bytes([self.0, self.1, self.2, 0])
+    }
+}
+
+/// Colors of the native frame (Windows 11 and newer), `None` keeps the
+/// system's.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TitlebarColors {
+    pub caption: Option<Rgb>,
+    pub text: Option<Rgb>,
+    pub border: Option<Rgb>,
+}
+
+/// Material the compositor draws behind a window (Windows 11 22H2 and newer).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Backdrop {
+    /// Plain window background.
+    #[default]
+    None,
+    /// Tinted with the desktop wallpaper, for long-lived main windows.
+    Mica,
+    /// Mica with a stronger tint, for tabbed windows.
+    MicaAlt,
+    /// Blurs what is behind the window, for transient popups and menus.
+    Acrylic,
+}
+
+type SetWindowCompositionAttributeFn =
+    unsafe extern "system" fn(HWND, *mut WindowCompositionAttributeData) -> BOOL;
+
+// Undocumented, but the only way to get a blur behind windows since
+// Windows 10, where DwmEnableBlurBehindWindow only enables the alpha channel.
+static SET_WINDOW_COMPOSITION_ATTRIBUTE: LazyLock<Option<SetWindowCompositionAttributeFn>> =
+    LazyLock::new(|| unsafe {
+        let user32 = GetModuleHandleW(w!("user32.dll")).ok()?;
+        let function = GetProcAddress(user32, s!("SetWindowCompositionAttribute"))?;
+        Some(mem::transmute::<
+            unsafe extern "system" fn() -> isize,
+            SetWindowCompositionAttributeFn,
+        >(function))
+    });
+
+const WCA_ACCENT_POLICY: u32 = 19;
+const ACCENT_DISABLED: u32 = 0;
+const ACCENT_ENABLE_BLURBEHIND: u32 = 3;
+
+#[repr(C)]
+struct AccentPolicy {
+    accent_state: u32,
+    accent_flags: u32,
+    gradient_color: u32,
+    animation_id: u32,
+}
+
+#[repr(C)]
+struct WindowCompositionAttributeData {
+    attribute: u32,
+    data: *mut c_void,
+    size: usize,
+}
+
+/// Frame appearance handled by the compositor. Older versions of Windows
+/// ignore the attributes they don't know about.
+impl WindowsWindow {
+    pub fn set_corner_preference(&self, preference: CornerPreference) -> Result<()> {
+        let preference = match preference {
+            CornerPreference::Default => DWMWCP_DEFAULT,
+            CornerPreference::DoNotRound => DWMWCP_DONOTROUND,
+            CornerPreference::Round => DWMWCP_ROUND,
+            CornerPreference::RoundSmall => DWMWCP_ROUNDSMALL,
+        };
+        self.set_dwm_attribute(DWMWA_WINDOW_CORNER_PREFERENCE, &preference)
+    }
+
+    /// Draws `backdrop` behind the frame and the client area. It only shows
+    /// through where the application doesn't paint, so pair it with a
+    /// transparent window or a class without background brush.
+    pub fn set_backdrop(&self, backdrop: Backdrop) -> Result<()> {
+        let backdrop_type = match backdrop {
+            Backdrop::None => DWMSBT_NONE,
+            Backdrop::Mica => DWMSBT_MAINWINDOW,
+            Backdrop::MicaAlt => DWMSBT_TABBEDWINDOW,
+            Backdrop::Acrylic => DWMSBT_TRANSIENTWINDOW,
+        };
+        // Negative margins extend the frame, and its material, over the
+        // whole client area.
+        let inset = if backdrop == Backdrop::None { 0 } else { -1 };
+        let margins = MARGINS {
+            cxLeftWidth: inset,
+            cxRightWidth: inset,
+            cyTopHeight: inset,
+            cyBottomHeight: inset,
+        };
+        unsafe { DwmExtendFrameIntoClientArea(self.hwnd(), &margins)? };
+        self.set_dwm_attribute(DWMWA_SYSTEMBACKDROP_TYPE, &backdrop_type)
+    }
+
+    /// Casts the native drop shadow around an undecorated window, e.g. a
+    /// borderless popup. Decorated windows always have one.
+    ///
+    /// This extends the frame by a pixel into the client area, so it
+    /// replaces the margins set by [`set_backdrop`](Self::set_backdrop).
+    pub fn set_shadow(&self, shadow: bool) -> Result<()> {
+        let (policy, inset) = if shadow {
+            (DWMNCRP_ENABLED, 1)
+        } else {
+            (DWMNCRP_USEWINDOWSTYLE, 0)
+        };
+        self.set_dwm_attribute(DWMWA_NCRENDERING_POLICY, &policy)?;
+        let margins = MARGINS {
+            cxLeftWidth: inset,
+            cxRightWidth: inset,
+            cyTopHeight: inset,
+            cyBottomHeight: inset,
+        };
+        unsafe { DwmExtendFrameIntoClientArea(self.hwnd(), &margins)? };
+        Ok(())
+    }
+
+    /// Blurs what is behind the window, for frosted translucent overlays.
+    /// Only visible through a transparent window.
+    pub fn set_blur_behind(&self, blur: bool) -> Result<()> {
+        if let Some(set_window_composition_attribute) = *SET_WINDOW_COMPOSITION_ATTRIBUTE {
+            let mut policy = AccentPolicy {
+                accent_state: if blur {
+                    ACCENT_ENABLE_BLURBEHIND
+                } else {
+                    ACCENT_DISABLED
+                },
+                accent_flags: 0,
+                gradient_color: 0,
+                animation_id: 0,
+            };
+            let mut data = WindowCompositionAttributeData {
+                attribute: WCA_ACCENT_POLICY,
+                data: &mut policy as *mut AccentPolicy as *mut c_void,
+                size: mem::size_of::<AccentPolicy>(),
+            };
+            if !unsafe { set_window_composition_attribute(self.hwnd(), &mut data) }.as_bool() {
+                bail!("Could not set the accent policy of the window");
+            }
+            return Ok(());
+        }
+
+        // Windows 7 and 8 blur the whole client area
+        let blur_behind = DWM_BLURBEHIND {
+            dwFlags: DWM_BB_ENABLE,
+            fEnable: blur.into(),
+            hRgnBlur: HRGN::default(),
+            fTransitionOnMaximized: false.into(),
+        };
+        unsafe { DwmEnableBlurBehindWindow(self.hwnd(), &blur_behind)? };
+        Ok(())
+    }
+
+    /// Forces the title bar into `theme`, `None` follows the system setting
+    /// and its changes.
+    pub fn set_theme(&self, theme: Option<Theme>) -> Result<()> {
+        if let Some(state) = unsafe { window_state(self.hwnd()) } {
+            state.theme.set(theme);
+        }
+        self.apply_theme(theme.unwrap_or_else(system_theme))
+    }
+
+    /// The theme of the title bar, forced or followed.
+    pub fn theme(&self) -> Theme {
+        unsafe { window_state(self.hwnd()) }
+            .and_then(|state| state.theme.get())
+            .unwrap_or_else(system_theme)
+    }
+
+    pub(crate) fn apply_theme(&self, theme: Theme) -> Result<()> {
+        let dark = BOOL::from(theme == Theme::Dark);
+        self.set_dwm_attribute(DWMWA_USE_IMMERSIVE_DARK_MODE, &dark)
+    }
+
+    /// Follows a change of the system theme, returning the event to deliver
+    /// if it actually flipped.
+    pub(crate) fn system_theme_changed(&self) -> Option<WindowEvent> {
+        let state = unsafe { window_state(self.hwnd()) }?;
+        let theme = system_theme();
+        if state.system_theme.replace(Some(theme)) == Some(theme) {
+            return None;
+        }
+        if state.theme.get().is_none() {
+            let _ = self.apply_theme(theme);
+        }
+        Some(WindowEvent::ThemeChanged(theme))
+    }
+
+    /// Tints the title bar and border, e.g. with the colors of a brand,
+    /// without drawing a custom caption.
+    pub fn set_titlebar_colors(&self, colors: TitlebarColors) -> Result<()> {
+        for (attribute, color) in [
+            (DWMWA_CAPTION_COLOR, colors.caption),
+            (DWMWA_TEXT_COLOR, colors.text),
+            (DWMWA_BORDER_COLOR, colors.border),
+        ] {
+            let color = color.map_or(DWMWA_COLOR_DEFAULT, Rgb::colorref);
+            self.set_dwm_attribute(attribute, &color)?;
+        }
+        Ok(())
+    }
+
+    fn set_dwm_attribute<T>(&self, attribute: DWMWINDOWATTRIBUTE, value: &T) -> Result<()> {
+        unsafe {
+            DwmSetWindowAttribute(
+                self.hwnd(),
+                attribute,
+                value as *const T as *const c_void,
+                mem::size_of::<T>() as u32,
+            )?
+        };
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_colors_to_colorref() {
+        assert_eq!(Rgb(0x12, 0x34, 0x56).colorref(), 0x0056_3412);
+    }
+}
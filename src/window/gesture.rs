@@ -0,0 +1,174 @@
+use std::{f64::consts::PI, mem};
+
+use anyhow::{bail, Result};
+use windows::Win32::{
+    Foundation::LPARAM,
+    UI::{
+        Input::Touch::{
+            CloseGestureInfoHandle, GetGestureInfo, SetGestureConfig, GESTURECONFIG,
+            GESTURECONFIG_ID, GESTUREINFO, GID_PAN, GID_ROTATE, GID_ZOOM, HGESTUREINFO,
+        },
+        WindowsAndMessaging::GF_BEGIN,
+    },
+};
+
+use super::{windows::window_state, WindowsWindow};
+use crate::event::{Gesture, WindowEvent};
+
+// Config for every gesture at once, with every option of each
+const GID_ALL: GESTURECONFIG_ID = GESTURECONFIG_ID(0);
+const GC_ALLGESTURES: u32 = 1;
+
+/// Gestures recognized by the system from two-finger touches.
+impl WindowsWindow {
+    /// Delivers pans, pinches and rotations as [`WindowEvent::Gesture`],
+    /// e.g. for map and photo viewers. The system then no longer turns
+    /// them into scroll messages.
+    pub fn set_gestures(&self, enabled: bool) -> Result<()> {
+        let Some(state) = (unsafe { window_state(self.hwnd()) }) else {
+            bail!("Not a paneless window");
+        };
+        if enabled {
+            // Rotation and horizontal panning are off by default
+            let config = GESTURECONFIG {
+                dwID: GID_ALL,
+                dwWant: GC_ALLGESTURES,
+                dwBlock: 0,
+            };
+            unsafe {
+                SetGestureConfig(
+                    self.hwnd(),
+                    0,
+                    &[config],
+                    mem::size_of::<GESTURECONFIG>() as u32,
+                )?
+            };
+        }
+        state.gestures.set(enabled);
+        Ok(())
+    }
+
+    /// Events for a WM_GESTURE message, `None` to leave the message to
+    /// DefWindowProc.
+    pub(crate) fn gesture_message(&self, l_param: LPARAM) -> Option<Vec<WindowEvent>> {
+        let state = unsafe { window_state(self.hwnd()) }?;
+        if !state.gestures.get() {
+            return None;
+        }
+        let handle = HGESTUREINFO(l_param.0 as *mut _);
+        let mut info = GESTUREINFO {
+            cbSize: mem::size_of::<GESTUREINFO>() as u32,
+            ..Default::default()
+        };
+        // Left open for DefWindowProc when the message isn't consumed
+        unsafe { GetGestureInfo(handle, &mut info) }.ok()?;
+        unsafe {
+            let _ = CloseGestureInfoHandle(handle);
+        }
+
+        let gesture = gesture_delta(&state.last_gesture.replace(info), &info);
+        let event = gesture.map(|gesture| {
+            WindowEvent::Gesture(match gesture {
+                Gesture::PinchZoom { scale, x, y } => {
+                    let (x, y) = self.screen_to_client(x, y);
+                    Gesture::PinchZoom { scale, x, y }
+                }
+                Gesture::Rotate { angle, x, y } => {
+                    let (x, y) = self.screen_to_client(x, y);
+                    Gesture::Rotate { angle, x, y }
+                }
+                pan => pan,
+            })
+        });
+        Some(event.into_iter().collect())
+    }
+}
+
+/// Change since the previous message of the same gesture, with positions in
+/// screen coordinates. Messages only carry the state of the gesture.
+fn gesture_delta(last: &GESTUREINFO, info: &GESTUREINFO) -> Option<Gesture> {
+    if info.dwFlags & GF_BEGIN != 0 || info.dwID != last.dwID {
+        return None;
+    }
+    let (x, y) = (info.ptsLocation.x as i32, info.ptsLocation.y as i32);
+    match GESTURECONFIG_ID(info.dwID) {
+        GID_PAN => Some(Gesture::Pan {
+            dx: x - last.ptsLocation.x as i32,
+            dy: y - last.ptsLocation.y as i32,
+        }),
+        // The distance between the fingers
+        GID_ZOOM if last.ullArguments != 0 => Some(Gesture::PinchZoom {
+            scale: info.ullArguments as f64 / last.ullArguments as f64,
+            x,
+            y,
+        }),
+        // The angle of the fingers when starting, then the rotation since
+        GID_ROTATE => {
+            let last_angle = if last.dwFlags & GF_BEGIN != 0 {
+                0.0
+            } else {
+                rotation_angle(last.ullArguments)
+            };
+            Some(Gesture::Rotate {
+                angle: rotation_angle(info.ullArguments) - last_angle,
+                x,
+                y,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// GID_ROTATE_ANGLE_FROM_ARGUMENT
+fn rotation_angle(argument: u64) -> f64 {
+    (argument & 0xffff) as f64 / 65535.0 * 4.0 * PI - 2.0 * PI
+}
+
+#[cfg(test)]
+mod tests {
+    use windows::Win32::Foundation::POINTS;
+
+    use super::*;
+
+    fn info(id: GESTURECONFIG_ID, flags: u32, location: (i16, i16), arguments: u64) -> GESTUREINFO {
+        GESTUREINFO {
+            dwID: id.0,
+            dwFlags: flags,
+            ptsLocation: POINTS {
+                x: location.0,
+                y: location.1,
+            },
+            ullArguments: arguments,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn reports_changes_since_the_last_message() {
+        let begin = info(GID_ZOOM, GF_BEGIN, (10, 10), 100);
+        assert_eq!(gesture_delta(&GESTUREINFO::default(), &begin), None);
+        assert_eq!(
+            gesture_delta(&begin, &info(GID_ZOOM, 0, (12, 10), 150)),
+            Some(Gesture::PinchZoom {
+                scale: 1.5,
+                x: 12,
+                y: 10
+            })
+        );
+
+        let pan = info(GID_PAN, 0, (10, 10), 0);
+        assert_eq!(
+            gesture_delta(&pan, &info(GID_PAN, 0, (4, 13), 0)),
+            Some(Gesture::Pan { dx: -6, dy: 3 })
+        );
+
+        // Half of the range is no rotation
+        let begin = info(GID_ROTATE, GF_BEGIN, (0, 0), 12345);
+        let Some(Gesture::Rotate { angle, .. }) =
+            gesture_delta(&begin, &info(GID_ROTATE, 0, (0, 0), 32768))
+        else {
+            panic!("Expected a rotation");
+        };
+        assert!(angle.abs() < 0.001);
+    }
+}
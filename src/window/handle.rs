@@ -0,0 +1,37 @@
+use std::num::NonZeroIsize;
+
+use raw_window_handle::{
+    DisplayHandle, HandleError, HasDisplayHandle, HasWindowHandle, RawWindowHandle,
+    Win32WindowHandle, WindowHandle,
+};
+use windows::Win32::UI::WindowsAndMessaging::{GetWindowLongPtrW, IsWindow, GWLP_HINSTANCE};
+
+use super::WindowsWindow;
+use crate::event_loop::EventLoop;
+
+/// Lets wgpu, glutin, softbuffer and the like render into the window.
+impl HasWindowHandle for WindowsWindow {
+    fn window_handle(&self) -> Result<WindowHandle<'_>, HandleError> {
+        // The window isn't owned by the handle, it may be gone
+        if !unsafe { IsWindow(self.hwnd()) }.as_bool() {
+            return Err(HandleError::Unavailable);
+        }
+        let hwnd = NonZeroIsize::new(self.hwnd().0 as isize).ok_or(HandleError::Unavailable)?;
+        let mut handle = Win32WindowHandle::new(hwnd);
+        handle.hinstance =
+            NonZeroIsize::new(unsafe { GetWindowLongPtrW(self.hwnd(), GWLP_HINSTANCE) });
+        Ok(unsafe { WindowHandle::borrow_raw(RawWindowHandle::Win32(handle)) })
+    }
+}
+
+impl HasDisplayHandle for WindowsWindow {
+    fn display_handle(&self) -> Result<DisplayHandle<'_>, HandleError> {
+        Ok(DisplayHandle::windows())
+    }
+}
+
+impl<T> HasDisplayHandle for EventLoop<T> {
+    fn display_handle(&self) -> Result<DisplayHandle<'_>, HandleError> {
+        Ok(DisplayHandle::windows())
+    }
+}
@@ -0,0 +1,130 @@
+use std::{path::Path, rc::Rc};
+
+use anyhow::{bail, Result};
+use windows::{
+    core::PCWSTR,
+    Win32::{
+        Foundation::HINSTANCE,
+        UI::WindowsAndMessaging::{
+            CreateIcon, DestroyIcon, LoadImageW, HICON, IMAGE_ICON, LR_DEFAULTSIZE, LR_LOADFROMFILE,
+        },
+    },
+};
+
+use crate::utils::strings::str_to_wstr;
+
+/// A window icon. Cheap to clone, the icon is destroyed with the last clone,
+/// and windows keep the clone they were given alive.
+#[derive(Debug, Clone)]
+pub struct Icon {
+    inner: Rc<OwnedIcon>,
+}
+
+#[derive(Debug)]
+struct OwnedIcon(HICON);
+
+impl Drop for OwnedIcon {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = DestroyIcon(self.0);
+        }
+    }
+}
+
+impl Icon {
+    /// Creates an icon from `width` by `height` pixels of straight RGBA.
+    pub fn from_rgba(rgba: &[u8], width: u32, height: u32) -> Result<Self> {
+        let bgra = rgba_to_bgra(rgba, width, height)?;
+        let and_mask = and_mask(rgba, width, height);
+        let hicon = unsafe {
+            CreateIcon(
+                HINSTANCE::default(),
+                width as i32,
+                height as i32,
+                1,
+                32,
+                and_mask.as_ptr(),
+                bgra.as_ptr(),
+            )?
+        };
+        Ok(Self::from_hicon(hicon))
+    }
+
+    /// Loads an `.ico` file, picking the image closest to `size` or to the
+    /// system's default icon size.
+    pub fn from_path(path: impl AsRef<Path>, size: Option<(u32, u32)>) -> Result<Self> {
+        let path = str_to_wstr(&path.as_ref().to_string_lossy());
+        let (width, height) = size.unwrap_or((0, 0));
+        let flags = if size.is_some() {
+            LR_LOADFROMFILE
+        } else {
+            LR_LOADFROMFILE | LR_DEFAULTSIZE
+        };
+        let handle = unsafe {
+            LoadImageW(
+                HINSTANCE::default(),
+                PCWSTR(path.as_ptr()),
+                IMAGE_ICON,
+                width as i32,
+                height as i32,
+                flags,
+            )?
+        };
+        Ok(Self::from_hicon(HICON(handle.0)))
+    }
+
+    fn from_hicon(hicon: HICON) -> Self {
+        Self {
+            inner: Rc::new(OwnedIcon(hicon)),
+        }
+    }
+
+    pub fn hicon(&self) -> HICON {
+        self.inner.0
+    }
+}
+
+/// Checks the size of an image and converts it to the BGRA GDI takes.
+pub(super) fn rgba_to_bgra(rgba: &[u8], width: u32, height: u32) -> Result<Vec<u8>> {
+    if width == 0 || height == 0 || rgba.len() != width as usize * height as usize * 4 {
+        bail!(
+            "Expected {} bytes of RGBA for a {}x{} image, got {}",
+            width as usize * height as usize * 4,
+            width,
+            height,
+            rgba.len()
+        );
+    }
+    Ok(rgba
+        .chunks_exact(4)
+        .flat_map(|pixel| [pixel[2], pixel[1], pixel[0], pixel[3]])
+        .collect())
+}
+
+/// Monochrome mask of an icon, set bits are transparent. Rows are padded to
+/// 16 bits.
+pub(super) fn and_mask(rgba: &[u8], width: u32, height: u32) -> Vec<u8> {
+    let stride = (width as usize).div_ceil(16) * 2;
+    let mut mask = vec![0; stride * height as usize];
+    for (i, pixel) in rgba.chunks_exact(4).enumerate() {
+        if pixel[3] == 0 {
+            let (x, y) = (i % width as usize, i / width as usize);
+            mask[y * stride + x / 8] |= 0x80 >> (x % 8);
+        }
+    }
+    mask
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masks_transparent_pixels() {
+        let mut rgba = [0xff; 3 * 2 * 4];
+        // Pixel (1, 0) and (2, 1) are transparent
+        rgba[4 + 3] = 0;
+        rgba[(3 + 2) * 4 + 3] = 0;
+        assert_eq!(and_mask(&rgba, 3, 2), [0b0100_0000, 0, 0b0010_0000, 0]);
+    }
+}
@@ -0,0 +1,169 @@
+use std::ffi::c_void;
+
+use anyhow::{bail, Result};
+use windows::Win32::{
+    Foundation::{LPARAM, POINT, RECT},
+    UI::{
+        Input::Ime::{
+            ImmGetCompositionStringW, ImmGetContext, ImmReleaseContext, ImmSetCandidateWindow,
+            ImmSetCompositionWindow, CANDIDATEFORM, CFS_EXCLUDE, CFS_POINT, COMPOSITIONFORM,
+            GCS_COMPSTR, GCS_CURSORPOS, GCS_RESULTSTR, HIMC, IME_COMPOSITION_STRING,
+        },
+        WindowsAndMessaging::{WM_IME_COMPOSITION, WM_IME_ENDCOMPOSITION, WM_IME_STARTCOMPOSITION},
+    },
+};
+
+use super::{windows::window_state, WindowsWindow};
+use crate::event::{Ime, WindowEvent};
+
+/// Input method editors, which compose CJK text out of several keystrokes.
+impl WindowsWindow {
+    /// Delivers the text being composed and the committed text as
+    /// [`WindowEvent::Ime`], for applications drawing the composition inline.
+    /// The IME then no longer shows its own composition window, and
+    /// committed text no longer arrives as
+    /// [`WindowEvent::ReceivedCharacter`].
+    pub fn set_ime_events(&self, enabled: bool) -> Result<()> {
+        let Some(state) = (unsafe { window_state(self.hwnd()) }) else {
+            bail!("Not a paneless window");
+        };
+        state.ime_events.set(enabled);
+        Ok(())
+    }
+
+    /// Tells the IME where the text cursor is, in client coordinates, so it
+    /// places its windows next to it without covering it.
+    pub fn set_ime_cursor_area(&self, position: (i32, i32), size: (u32, u32)) -> Result<()> {
+        let Some(state) = (unsafe { window_state(self.hwnd()) }) else {
+            bail!("Not a paneless window");
+        };
+        let (x, y) = position;
+        // Huge areas saturate rather than overflow
+        let extent = |size: u32| i32::try_from(size).unwrap_or(i32::MAX);
+        state.ime_cursor_area.set(Some(RECT {
+            left: x,
+            top: y,
+            right: x.saturating_add(extent(size.0)),
+            bottom: y.saturating_add(extent(size.1)),
+        }));
+        self.apply_ime_cursor_area();
+        Ok(())
+    }
+
+    fn apply_ime_cursor_area(&self) {
+        let Some(area) =
+            unsafe { window_state(self.hwnd()) }.and_then(|state| state.ime_cursor_area.get())
+        else {
+            return;
+        };
+        let position = POINT {
+            x: area.left,
+            y: area.top,
+        };
+        self.with_input_context(|himc| unsafe {
+            let composition = COMPOSITIONFORM {
+                dwStyle: CFS_POINT,
+                ptCurrentPos: position,
+                rcArea: RECT::default(),
+            };
+            let _ = ImmSetCompositionWindow(himc, &composition);
+            let candidate = CANDIDATEFORM {
+                dwIndex: 0,
+                dwStyle: CFS_EXCLUDE,
+                ptCurrentPos: position,
+                rcArea: area,
+            };
+            let _ = ImmSetCandidateWindow(himc, &candidate);
+        });
+    }
+
+    /// Events for an IME message, `None` to leave the message to the IME.
+    pub(crate) fn ime_message(&self, msg: u32, l_param: LPARAM) -> Option<Vec<WindowEvent>> {
+        if msg == WM_IME_STARTCOMPOSITION {
+            self.apply_ime_cursor_area();
+        }
+        let state = unsafe { window_state(self.hwnd()) }?;
+        if !state.ime_events.get() {
+            return None;
+        }
+
+        let mut events = Vec::new();
+        match msg {
+            WM_IME_COMPOSITION => self.with_input_context(|himc| {
+                let changed = IME_COMPOSITION_STRING(l_param.0 as u32);
+                if changed.contains(GCS_RESULTSTR) {
+                    if let Some(text) = composition_string(himc, GCS_RESULTSTR) {
+                        events.push(Ime::Commit(String::from_utf16_lossy(&text)));
+                    }
+                }
+                if changed.contains(GCS_COMPSTR) {
+                    if let Some(text) = composition_string(himc, GCS_COMPSTR) {
+                        let cursor =
+                            unsafe { ImmGetCompositionStringW(himc, GCS_CURSORPOS, None, 0) };
+                        events.push(Ime::Preedit {
+                            cursor: usize::try_from(cursor)
+                                .ok()
+                                .map(|cursor| utf16_to_byte_offset(&text, cursor)),
+                            text: String::from_utf16_lossy(&text),
+                        });
+                    }
+                }
+            }),
+            WM_IME_ENDCOMPOSITION => events.push(Ime::Preedit {
+                text: String::new(),
+                cursor: None,
+            }),
+            _ => (),
+        }
+        Some(events.into_iter().map(WindowEvent::Ime).collect())
+    }
+
+    fn with_input_context(&self, f: impl FnOnce(HIMC)) {
+        let himc = unsafe { ImmGetContext(self.hwnd()) };
+        if himc.is_invalid() {
+            return;
+        }
+        f(himc);
+        unsafe {
+            let _ = ImmReleaseContext(self.hwnd(), himc);
+        }
+    }
+}
+
+/// Reads one of the strings of the current composition.
+fn composition_string(himc: HIMC, kind: IME_COMPOSITION_STRING) -> Option<Vec<u16>> {
+    // Lengths are in bytes
+    let length = unsafe { ImmGetCompositionStringW(himc, kind, None, 0) };
+    let mut text = vec![0u16; usize::try_from(length).ok()? / 2];
+    let length = unsafe {
+        ImmGetCompositionStringW(
+            himc,
+            kind,
+            Some(text.as_mut_ptr() as *mut c_void),
+            length as u32,
+        )
+    };
+    text.truncate(usize::try_from(length).ok()? / 2);
+    Some(text)
+}
+
+/// Byte offset in the UTF-8 form of `text` of its UTF-16 unit `index`.
+fn utf16_to_byte_offset(text: &[u16], index: usize) -> usize {
+    char::decode_utf16(text[..index.min(text.len())].iter().copied())
+        .map(|character| character.map_or(3, char::len_utf8))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_the_composition_cursor_to_bytes() {
+        let text: Vec<u16> = "日本a".encode_utf16().collect();
+        assert_eq!(utf16_to_byte_offset(&text, 0), 0);
+        assert_eq!(utf16_to_byte_offset(&text, 2), 6);
+        assert_eq!(utf16_to_byte_offset(&text, 3), 7);
+        assert_eq!(utf16_to_byte_offset(&text, 9), 7);
+    }
+}
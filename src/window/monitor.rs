@@ -0,0 +1,59 @@
+use anyhow::{bail, Result};
+use windows::Win32::{
+    Foundation::{HWND, POINT, RECT},
+    Graphics::Gdi::{
+        GetMonitorInfoW, MonitorFromPoint, MonitorFromRect, MonitorFromWindow, HMONITOR,
+        MONITORINFO, MONITOR_DEFAULTTONEAREST, MONITOR_DEFAULTTOPRIMARY,
+    },
+};
+
+/// A display attached to the desktop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MonitorHandle {
+    hmonitor: HMONITOR,
+}
+
+impl MonitorHandle {
+    pub fn primary() -> Self {
+        // The primary monitor always contains the origin
+        let hmonitor = unsafe { MonitorFromPoint(POINT { x: 0, y: 0 }, MONITOR_DEFAULTTOPRIMARY) };
+        Self { hmonitor }
+    }
+
+    /// The monitor showing the largest part of the window.
+    pub fn from_window(hwnd: HWND) -> Self {
+        let hmonitor = unsafe { MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST) };
+        Self { hmonitor }
+    }
+
+    /// The monitor overlapping `rect` the most, or the nearest one.
+    pub fn from_rect(rect: &RECT) -> Self {
+        let hmonitor = unsafe { MonitorFromRect(rect, MONITOR_DEFAULTTONEAREST) };
+        Self { hmonitor }
+    }
+
+    pub fn hmonitor(&self) -> HMONITOR {
+        self.hmonitor
+    }
+
+    /// The whole monitor, in virtual screen coordinates.
+    pub fn bounds(&self) -> Result<RECT> {
+        Ok(self.info()?.rcMonitor)
+    }
+
+    /// The monitor minus the taskbar and docked toolbars.
+    pub fn work_area(&self) -> Result<RECT> {
+        Ok(self.info()?.rcWork)
+    }
+
+    fn info(&self) -> Result<MONITORINFO> {
+        let mut info = MONITORINFO {
+            cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+            ..Default::default()
+        };
+        if !unsafe { GetMonitorInfoW(self.hmonitor, &mut info) }.as_bool() {
+            bail!("Could not get the monitor info of {:?}", self.hmonitor);
+        }
+        Ok(info)
+    }
+}
@@ -0,0 +1,232 @@
+use std::marker::PhantomData;
+
+use anyhow::{bail, Result};
+use windows::Win32::{
+    Foundation::{COLORREF, RECT},
+    Graphics::Gdi::{
+        CreatePen, CreateSolidBrush, DeleteObject, DrawTextW, Ellipse, FillRect, GetDC,
+        GetStockObject, GetSysColorBrush, LineTo, MoveToEx, Rectangle, ReleaseDC, SelectObject,
+        SetBkMode, SetTextColor, BACKGROUND_MODE, DRAW_TEXT_FORMAT, HBRUSH, HDC, HGDIOBJ, HPEN,
+        NULL_BRUSH, NULL_PEN, PS_SOLID, SYS_COLOR_INDEX, TRANSPARENT,
+    },
+    UI::WindowsAndMessaging::GetClientRect,
+};
+
+use super::{windows::window_state, Rgb, WindowsWindow};
+
+/// A GDI pen for outlines, deleted on drop.
+#[derive(Debug)]
+pub struct Pen(HPEN);
+
+impl Pen {
+    /// Solid pen `width` pixels wide, 0 for the thinnest line.
+    pub fn new(color: Rgb, width: u32) -> Result<Self> {
+        let pen = unsafe { CreatePen(PS_SOLID, width as i32, COLORREF(color.colorref())) };
+        if pen.is_invalid() {
+            bail!("Could not create a pen");
+        }
+        Ok(Self(pen))
+    }
+}
+
+impl Drop for Pen {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = DeleteObject(self.0);
+        }
+    }
+}
+
+/// A GDI brush for fills, deleted on drop unless the system owns it.
+#[derive(Debug)]
+pub struct Brush {
+    handle: HBRUSH,
+    owned: bool,
+}
+
+impl Brush {
+    pub fn solid(color: Rgb) -> Result<Self> {
+        let handle = unsafe { CreateSolidBrush(COLORREF(color.colorref())) };
+        if handle.is_invalid() {
+            bail!("Could not create a brush");
+        }
+        Ok(Self {
+            handle,
+            owned: true,
+        })
+    }
+
+    /// The system's brush for a color of the theme, e.g. `COLOR_WINDOW`.
+    pub fn system(color: SYS_COLOR_INDEX) -> Self {
+        Self {
+            handle: unsafe { GetSysColorBrush(color) },
+            owned: false,
+        }
+    }
+}
+
+impl Drop for Brush {
+    fn drop(&mut self) {
+        if self.owned {
+            unsafe {
+                let _ = DeleteObject(self.handle);
+            }
+        }
+    }
+}
+
+/// GDI drawing on a window's client area, in client coordinates, see
+/// [`WindowsWindow::paint`].
+#[derive(Debug)]
+pub struct Painter<'a> {
+    hdc: HDC,
+    erase: bool,
+    dirty: RECT,
+    // Only valid while the paint cycle or the borrowed DC is
+    _hdc: PhantomData<&'a HDC>,
+}
+
+/// Object selected into a DC, the previous one restored on drop.
+struct Selected {
+    hdc: HDC,
+    previous: HGDIOBJ,
+}
+
+impl Selected {
+    fn new(hdc: HDC, object: HGDIOBJ) -> Self {
+        Self {
+            hdc,
+            previous: unsafe { SelectObject(hdc, object) },
+        }
+    }
+}
+
+impl Drop for Selected {
+    fn drop(&mut self) {
+        unsafe {
+            SelectObject(self.hdc, self.previous);
+        }
+    }
+}
+
+impl Painter<'_> {
+    /// `hdc` must stay valid as long as the painter.
+    pub(crate) unsafe fn new(hdc: HDC, erase: bool, dirty: RECT) -> Self {
+        Self {
+            hdc,
+            erase,
+            dirty,
+            _hdc: PhantomData,
+        }
+    }
+
+    pub(crate) fn cycle(&self) -> (HDC, bool, RECT) {
+        (self.hdc, self.erase, self.dirty)
+    }
+
+    /// Area to redraw, drawing elsewhere is clipped in a paint cycle.
+    pub fn dirty_rect(&self) -> RECT {
+        self.dirty
+    }
+
+    /// Whether the background still needs erasing, when the window's class
+    /// has no background brush.
+    pub fn needs_erase(&self) -> bool {
+        self.erase
+    }
+
+    pub fn line(&self, from: (i32, i32), to: (i32, i32), pen: &Pen) -> Result<()> {
+        let _pen = Selected::new(self.hdc, pen.0.into());
+        unsafe {
+            MoveToEx(self.hdc, from.0, from.1, None).ok()?;
+            LineTo(self.hdc, to.0, to.1).ok()?;
+        }
+        Ok(())
+    }
+
+    pub fn fill_rect(&self, rect: RECT, brush: &Brush) -> Result<()> {
+        if unsafe { FillRect(self.hdc, &rect, brush.handle) } == 0 {
+            bail!("Could not fill {:?}", rect);
+        }
+        Ok(())
+    }
+
+    /// Rectangle outlined with `pen` and filled with `brush`, `None` to
+    /// leave either out.
+    pub fn rectangle(&self, rect: RECT, pen: Option<&Pen>, brush: Option<&Brush>) -> Result<()> {
+        let _selected = self.select(pen, brush);
+        unsafe { Rectangle(self.hdc, rect.left, rect.top, rect.right, rect.bottom).ok()? };
+        Ok(())
+    }
+
+    /// Ellipse inscribed in `rect`, outlined with `pen` and filled with
+    /// `brush`.
+    pub fn ellipse(&self, rect: RECT, pen: Option<&Pen>, brush: Option<&Brush>) -> Result<()> {
+        let _selected = self.select(pen, brush);
+        unsafe { Ellipse(self.hdc, rect.left, rect.top, rect.right, rect.bottom).ok()? };
+        Ok(())
+    }
+
+    /// Draws `text` in `rect` with the window's font and `DrawTextW` flags,
+    /// e.g. `DT_CENTER | DT_VCENTER | DT_SINGLELINE`. Returns the height of
+    /// the text.
+    pub fn text(
+        &self,
+        text: &str,
+        rect: RECT,
+        color: Rgb,
+        format: DRAW_TEXT_FORMAT,
+    ) -> Result<i32> {
+        let mut text: Vec<u16> = text.encode_utf16().collect();
+        let mut rect = rect;
+        let height = unsafe {
+            let previous_color = SetTextColor(self.hdc, COLORREF(color.colorref()));
+            let previous_mode = SetBkMode(self.hdc, TRANSPARENT);
+            let height = DrawTextW(self.hdc, &mut text, &mut rect, format);
+            SetBkMode(self.hdc, BACKGROUND_MODE(previous_mode as u32));
+            SetTextColor(self.hdc, previous_color);
+            height
+        };
+        if height == 0 && !text.is_empty() {
+            bail!("Could not draw the text");
+        }
+        Ok(height)
+    }
+
+    fn select(&self, pen: Option<&Pen>, brush: Option<&Brush>) -> (Selected, Selected) {
+        let pen = pen.map_or_else(|| unsafe { GetStockObject(NULL_PEN) }, |pen| pen.0.into());
+        let brush = brush.map_or_else(
+            || unsafe { GetStockObject(NULL_BRUSH) },
+            |brush| brush.handle.into(),
+        );
+        (Selected::new(self.hdc, pen), Selected::new(self.hdc, brush))
+    }
+}
+
+impl WindowsWindow {
+    /// Draws on the client area with GDI. On
+    /// [`WindowEvent::RedrawRequested`](crate::event::WindowEvent::RedrawRequested)
+    /// this draws in the open paint cycle. Elsewhere it draws right away,
+    /// until the next repaint.
+    pub fn paint<T>(&self, f: impl FnOnce(&Painter) -> Result<T>) -> Result<T> {
+        let Some(state) = (unsafe { window_state(self.hwnd()) }) else {
+            bail!("Not a paneless window");
+        };
+        if let Some((hdc, erase, dirty)) = state.painting.get() {
+            return f(&unsafe { Painter::new(hdc, erase, dirty) });
+        }
+
+        let hdc = unsafe { GetDC(self.hwnd()) };
+        if hdc.is_invalid() {
+            bail!("Could not get the device context of the window");
+        }
+        let mut client = RECT::default();
+        let output = unsafe { GetClientRect(self.hwnd(), &mut client) }
+            .map_err(Into::into)
+            .and_then(|()| f(&unsafe { Painter::new(hdc, false, client) }));
+        unsafe {
+            ReleaseDC(self.hwnd(), hdc);
+        }
+        output
+    }
+}
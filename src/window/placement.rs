@@ -0,0 +1,155 @@
+use anyhow::{bail, Result};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use windows::Win32::{
+    Foundation::RECT,
+    UI::WindowsAndMessaging::{
+        GetWindowPlacement, SetWindowPlacement, SW_SHOWMAXIMIZED, SW_SHOWMINIMIZED, SW_SHOWNORMAL,
+        WINDOWPLACEMENT, WPF_RESTORETOMAXIMIZED, WS_EX_TOOLWINDOW,
+    },
+};
+
+use super::{MonitorHandle, WindowsWindow};
+
+/// Size, position and maximized state of a window, to save on exit and
+/// restore on the next run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct WindowPlacement {
+    /// Top-left corner of the frame when restored, in screen coordinates.
+    pub position: (i32, i32),
+    /// Size of the frame when restored.
+    pub size: (u32, u32),
+    /// Whether the window is maximized, or was before being minimized.
+    pub maximized: bool,
+}
+
+impl WindowsWindow {
+    /// Where the window goes when restored, even while it is maximized or
+    /// minimized.
+    pub fn placement(&self) -> Result<WindowPlacement> {
+        let mut placement = WINDOWPLACEMENT {
+            length: std::mem::size_of::<WINDOWPLACEMENT>() as u32,
+            ..Default::default()
+        };
+        unsafe { GetWindowPlacement(self.hwnd(), &mut placement)? };
+        let (dx, dy) = self.workspace_offset()?;
+        let rect = placement.rcNormalPosition;
+        let show_cmd = placement.showCmd as i32;
+        Ok(WindowPlacement {
+            position: (rect.left + dx, rect.top + dy),
+            size: (
+                (rect.right - rect.left).max(0) as u32,
+                (rect.bottom - rect.top).max(0) as u32,
+            ),
+            maximized: show_cmd == SW_SHOWMAXIMIZED.0
+                || (show_cmd == SW_SHOWMINIMIZED.0
+                    && placement.flags.contains(WPF_RESTORETOMAXIMIZED)),
+        })
+    }
+
+    /// Moves the window back to a saved placement and shows it. The window
+    /// is pulled onto the nearest monitor if the saved one is gone or got
+    /// smaller.
+    pub fn set_placement(&self, placement: WindowPlacement) -> Result<()> {
+        let rect = placement_rect(placement.position, placement.size)?;
+        let rect = clamp_to(rect, MonitorHandle::from_rect(&rect).work_area()?);
+        let (dx, dy) = self.workspace_offset()?;
+        let show_cmd = if placement.maximized {
+            SW_SHOWMAXIMIZED
+        } else {
+            SW_SHOWNORMAL
+        };
+        let placement = WINDOWPLACEMENT {
+            length: std::mem::size_of::<WINDOWPLACEMENT>() as u32,
+            showCmd: show_cmd.0 as u32,
+            rcNormalPosition: RECT {
+                left: rect.left - dx,
+                top: rect.top - dy,
+                right: rect.right - dx,
+                bottom: rect.bottom - dy,
+            },
+            ..Default::default()
+        };
+        unsafe { SetWindowPlacement(self.hwnd(), &placement)? };
+        Ok(())
+    }
+
+    /// Window placements are in workspace coordinates, relative to the work
+    /// area of the primary monitor, except for tool windows.
+    fn workspace_offset(&self) -> Result<(i32, i32)> {
+        if self.ex_style().contains(WS_EX_TOOLWINDOW) {
+            return Ok((0, 0));
+        }
+        let primary = MonitorHandle::primary();
+        let (bounds, work_area) = (primary.bounds()?, primary.work_area()?);
+        Ok((work_area.left - bounds.left, work_area.top - bounds.top))
+    }
+}
+
+/// Frame of a placement, which may come from a corrupt file. Sizes beyond
+/// `i32` are rejected, positions near the edge of it saturate.
+fn placement_rect(position: (i32, i32), size: (u32, u32)) -> Result<RECT> {
+    let (Ok(width), Ok(height)) = (i32::try_from(size.0), i32::try_from(size.1)) else {
+        bail!("Placement size {:?} is out of range", size);
+    };
+    Ok(RECT {
+        left: position.0,
+        top: position.1,
+        right: position.0.saturating_add(width),
+        bottom: position.1.saturating_add(height),
+    })
+}
+
+/// Moves `rect` inside `area`, shrinking it first if it doesn't fit.
+fn clamp_to(rect: RECT, area: RECT) -> RECT {
+    let width = (rect.right - rect.left).min(area.right - area.left);
+    let height = (rect.bottom - rect.top).min(area.bottom - area.top);
+    let left = rect.left.clamp(area.left, area.right - width);
+    let top = rect.top.clamp(area.top, area.bottom - height);
+    RECT {
+        left,
+        top,
+        right: left + width,
+        bottom: top + height,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clamps_placements_into_the_work_area() {
+        let rect = |left, top, right, bottom| RECT {
+            left,
+            top,
+            right,
+            bottom,
+        };
+        let work_area = rect(0, 0, 1920, 1040);
+        assert_eq!(
+            clamp_to(rect(100, 100, 900, 700), work_area),
+            rect(100, 100, 900, 700)
+        );
+        assert_eq!(
+            clamp_to(rect(2500, -50, 3300, 550), work_area),
+            rect(1120, 0, 1920, 600)
+        );
+        assert_eq!(clamp_to(rect(-10, 0, 2000, 1200), work_area), work_area);
+    }
+
+    #[test]
+    fn builds_rects_of_corrupt_placements() {
+        assert_eq!(
+            placement_rect((i32::MAX - 1, 0), (800, 600)).unwrap(),
+            RECT {
+                left: i32::MAX - 1,
+                top: 0,
+                right: i32::MAX,
+                bottom: 600,
+            }
+        );
+        assert!(placement_rect((0, 0), (u32::MAX, u32::MAX)).is_err());
+    }
+}
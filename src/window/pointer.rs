@@ -0,0 +1,210 @@
+use std::mem;
+
+use anyhow::Result;
+use windows::Win32::{
+    Foundation::{LPARAM, POINT, WPARAM},
+    UI::{
+        Input::{
+            Pointer::{
+                GetPointerPenInfo, GetPointerTouchInfo, GetPointerType, POINTER_FLAGS,
+                POINTER_FLAG_CANCELED, POINTER_FLAG_INCONTACT, POINTER_INFO, POINTER_PEN_INFO,
+                POINTER_TOUCH_INFO,
+            },
+            Touch::{
+                CloseTouchInputHandle, GetTouchInputInfo, RegisterTouchWindow,
+                UnregisterTouchWindow, HTOUCHINPUT, REGISTER_TOUCH_WINDOW_FLAGS, TOUCHEVENTF_DOWN,
+                TOUCHEVENTF_FLAGS, TOUCHEVENTF_MOVE, TOUCHEVENTF_UP, TOUCHINPUT,
+                TOUCHINPUTMASKF_CONTACTAREA,
+            },
+        },
+        WindowsAndMessaging::{
+            PEN_FLAG_BARREL, PEN_FLAG_ERASER, PEN_FLAG_INVERTED, PEN_MASK_PRESSURE,
+            PEN_MASK_ROTATION, PEN_MASK_TILT_X, PEN_MASK_TILT_Y, POINTER_INPUT_TYPE, PT_PEN,
+            PT_TOUCH, TOUCH_MASK_CONTACTAREA, WM_POINTERDOWN, WM_POINTERUP, WM_POINTERUPDATE,
+        },
+    },
+};
+
+use super::{windows::rect_size, WindowsWindow};
+use crate::event::{Pen, Touch, TouchPhase, WindowEvent};
+
+// Pens report pressure from 0 to 1024
+const PEN_MAX_PRESSURE: f32 = 1024.0;
+
+/// Touch screens and pens, reported through the pointer messages of
+/// Windows 8 and newer, or WM_TOUCH for windows registered for it.
+impl WindowsWindow {
+    /// Receives touches as WM_TOUCH instead of pointer messages, for touch
+    /// screens whose drivers only report those properly. The window then
+    /// gets no [`WindowEvent::Gesture`].
+    pub fn set_touch_messages(&self, enabled: bool) -> Result<()> {
+        unsafe {
+            if enabled {
+                RegisterTouchWindow(self.hwnd(), REGISTER_TOUCH_WINDOW_FLAGS(0))?;
+            } else {
+                UnregisterTouchWindow(self.hwnd())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Events for a WM_TOUCH message, one per contact, `None` to leave the
+    /// message to DefWindowProc.
+    pub(crate) fn touch_message(
+        &self,
+        w_param: WPARAM,
+        l_param: LPARAM,
+    ) -> Option<Vec<WindowEvent>> {
+        let handle = HTOUCHINPUT(l_param.0 as *mut _);
+        let mut inputs = vec![TOUCHINPUT::default(); w_param.0 & 0xffff];
+        unsafe {
+            GetTouchInputInfo(handle, &mut inputs, mem::size_of::<TOUCHINPUT>() as i32).ok()?;
+            let _ = CloseTouchInputHandle(handle);
+        }
+        let events = inputs
+            .iter()
+            .filter_map(|input| {
+                let phase = touch_input_phase(input.dwFlags)?;
+                // Hundredths of a pixel, on the screen
+                let (x, y) = self.screen_to_client(input.x / 100, input.y / 100);
+                let contact_size = (input.dwMask & TOUCHINPUTMASKF_CONTACTAREA).0 != 0;
+                Some(WindowEvent::Touch(Touch {
+                    id: input.dwID,
+                    phase,
+                    x,
+                    y,
+                    contact_size: contact_size
+                        .then_some((input.cxContact / 100, input.cyContact / 100)),
+                }))
+            })
+            .collect();
+        Some(events)
+    }
+
+    /// Event for a WM_POINTER* message. Mouse pointers are left to the
+    /// mouse messages.
+    pub(crate) fn pointer_event(&self, msg: u32, w_param: WPARAM) -> Option<WindowEvent> {
+        let id = (w_param.0 & 0xffff) as u32;
+        let mut pointer_type = POINTER_INPUT_TYPE::default();
+        unsafe { GetPointerType(id, &mut pointer_type) }.ok()?;
+        match pointer_type {
+            PT_TOUCH => self.touch_event(msg, id),
+            PT_PEN => self.pen_event(msg, id),
+            _ => None,
+        }
+    }
+
+    fn touch_event(&self, msg: u32, id: u32) -> Option<WindowEvent> {
+        let mut info = POINTER_TOUCH_INFO::default();
+        unsafe { GetPointerTouchInfo(id, &mut info) }.ok()?;
+        let pointer = &info.pointerInfo;
+        let phase = touch_phase(msg, pointer.pointerFlags)?;
+        let (x, y) = self.pointer_position(pointer);
+        let contact_size =
+            (info.touchMask & TOUCH_MASK_CONTACTAREA != 0).then(|| rect_size(&info.rcContact));
+        Some(WindowEvent::Touch(Touch {
+            id,
+            phase,
+            x,
+            y,
+            contact_size,
+        }))
+    }
+
+    fn pen_event(&self, msg: u32, id: u32) -> Option<WindowEvent> {
+        let mut info = POINTER_PEN_INFO::default();
+        unsafe { GetPointerPenInfo(id, &mut info) }.ok()?;
+        let pointer = &info.pointerInfo;
+        let flags = pointer.pointerFlags;
+        // Unlike fingers, pens are reported while hovering
+        let phase = touch_phase(msg, flags)
+            .or_else(|| (msg == WM_POINTERUPDATE).then_some(TouchPhase::Moved))?;
+        let (x, y) = self.pointer_position(pointer);
+        let has = |mask| info.penMask & mask != 0;
+        Some(WindowEvent::Pen(Pen {
+            id,
+            phase,
+            x,
+            y,
+            in_contact: flags.contains(POINTER_FLAG_INCONTACT),
+            pressure: has(PEN_MASK_PRESSURE).then(|| info.pressure as f32 / PEN_MAX_PRESSURE),
+            tilt: (
+                if has(PEN_MASK_TILT_X) { info.tiltX } else { 0 },
+                if has(PEN_MASK_TILT_Y) { info.tiltY } else { 0 },
+            ),
+            rotation: has(PEN_MASK_ROTATION).then_some(info.rotation),
+            barrel: info.penFlags & PEN_FLAG_BARREL != 0,
+            eraser: info.penFlags & (PEN_FLAG_INVERTED | PEN_FLAG_ERASER) != 0,
+        }))
+    }
+
+    fn pointer_position(&self, pointer: &POINTER_INFO) -> (i32, i32) {
+        let POINT { x, y } = pointer.ptPixelLocation;
+        self.screen_to_client(x, y)
+    }
+}
+
+/// Phase of a touch pointer message, `None` for updates of a pointer that
+/// isn't touching, like a finger hovering over some screens.
+fn touch_phase(msg: u32, flags: POINTER_FLAGS) -> Option<TouchPhase> {
+    if flags.contains(POINTER_FLAG_CANCELED) {
+        return Some(TouchPhase::Cancelled);
+    }
+    match msg {
+        WM_POINTERDOWN => Some(TouchPhase::Started),
+        WM_POINTERUPDATE if flags.contains(POINTER_FLAG_INCONTACT) => Some(TouchPhase::Moved),
+        WM_POINTERUP => Some(TouchPhase::Ended),
+        _ => None,
+    }
+}
+
+/// Phase of a WM_TOUCH contact, `None` for contacts only in range.
+fn touch_input_phase(flags: TOUCHEVENTF_FLAGS) -> Option<TouchPhase> {
+    if flags.contains(TOUCHEVENTF_DOWN) {
+        Some(TouchPhase::Started)
+    } else if flags.contains(TOUCHEVENTF_UP) {
+        Some(TouchPhase::Ended)
+    } else if flags.contains(TOUCHEVENTF_MOVE) {
+        Some(TouchPhase::Moved)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_touch_phases() {
+        assert_eq!(
+            touch_phase(WM_POINTERDOWN, POINTER_FLAG_INCONTACT),
+            Some(TouchPhase::Started)
+        );
+        assert_eq!(
+            touch_phase(WM_POINTERUPDATE, POINTER_FLAG_INCONTACT),
+            Some(TouchPhase::Moved)
+        );
+        assert_eq!(touch_phase(WM_POINTERUPDATE, POINTER_FLAGS(0)), None);
+        assert_eq!(
+            touch_phase(WM_POINTERUP, POINTER_FLAG_CANCELED),
+            Some(TouchPhase::Cancelled)
+        );
+    }
+
+    #[test]
+    fn tracks_touch_input_phases() {
+        use windows::Win32::UI::Input::Touch::{TOUCHEVENTF_INRANGE, TOUCHEVENTF_PRIMARY};
+
+        assert_eq!(
+            touch_input_phase(TOUCHEVENTF_DOWN | TOUCHEVENTF_PRIMARY),
+            Some(TouchPhase::Started)
+        );
+        assert_eq!(
+            touch_input_phase(TOUCHEVENTF_MOVE | TOUCHEVENTF_INRANGE),
+            Some(TouchPhase::Moved)
+        );
+        assert_eq!(touch_input_phase(TOUCHEVENTF_UP), Some(TouchPhase::Ended));
+        assert_eq!(touch_input_phase(TOUCHEVENTF_INRANGE), None);
+    }
+}
@@ -0,0 +1,265 @@
+use std::{ffi::c_void, mem, slice};
+
+use anyhow::Result;
+use windows::Win32::{
+    Foundation::{HANDLE, HWND, LPARAM, WPARAM},
+    UI::{
+        Input::{
+            GetRawInputData, GetRawInputDeviceInfoW,
+            KeyboardAndMouse::{VK_NUMLOCK, VK_PAUSE},
+            RegisterRawInputDevices, HRAWINPUT, MOUSE_MOVE_ABSOLUTE, RAWINPUT, RAWINPUTDEVICE,
+            RAWINPUTHEADER, RAWKEYBOARD, RIDEV_DEVNOTIFY, RIDEV_INPUTSINK, RIDEV_REMOVE,
+            RIDI_DEVICEINFO, RID_DEVICE_INFO, RID_INPUT, RIM_TYPEHID, RIM_TYPEKEYBOARD,
+            RIM_TYPEMOUSE,
+        },
+        WindowsAndMessaging::{GIDC_ARRIVAL, GIDC_REMOVAL, RI_KEY_BREAK, RI_KEY_E0, RI_KEY_E1},
+    },
+};
+
+use super::WindowsWindow;
+use crate::{
+    event::{DeviceEvent, DeviceId, DeviceKind, ElementState, Event},
+    keyboard::KeyCode,
+};
+
+// HID usages of the generic desktop page
+const USAGE_PAGE_GENERIC_DESKTOP: u16 = 0x01;
+const USAGE_MOUSE: u16 = 0x02;
+const USAGE_KEYBOARD: u16 = 0x06;
+
+/// When a window registered for raw input receives it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RawInputMode {
+    /// While the application is in the foreground.
+    Foreground,
+    /// Even while another application has the focus, e.g. for hotkey
+    /// daemons and input diagnostics.
+    Background,
+}
+
+/// Input straight from the devices, delivered as [`Event::DeviceEvent`]
+/// before any layout or acceleration is applied. Registrations are per
+/// application: the last window registering a kind of device receives all
+/// of its input.
+///
+/// Devices of a registered kind are also reported as
+/// [`DeviceEvent::Added`] and [`DeviceEvent::Removed`] when plugged in or
+/// out, and those already connected as added when registering.
+impl WindowsWindow {
+    /// Reports every key press and release as [`DeviceEvent::Key`], `None`
+    /// stops.
+    pub fn set_raw_keyboard(&self, mode: Option<RawInputMode>) -> Result<()> {
+        self.register_raw_input(USAGE_PAGE_GENERIC_DESKTOP, USAGE_KEYBOARD, mode)
+    }
+
+    /// Reports the mouse's own movement as [`DeviceEvent::MouseMotion`],
+    /// without pointer acceleration and regardless of where the cursor is or
+    /// whether it is stuck at a screen edge, e.g. to turn a first person
+    /// camera. `None` stops.
+    pub fn set_raw_mouse(&self, mode: Option<RawInputMode>) -> Result<()> {
+        self.register_raw_input(USAGE_PAGE_GENERIC_DESKTOP, USAGE_MOUSE, mode)
+    }
+
+    /// Reports the input reports of HID devices with this top level usage
+    /// as [`DeviceEvent::HidReport`], e.g. page 0x01 usage 0x04 for
+    /// joysticks or page 0x01 usage 0x08 for 3D mice. `None` stops.
+    pub fn set_raw_hid(
+        &self,
+        usage_page: u16,
+        usage: u16,
+        mode: Option<RawInputMode>,
+    ) -> Result<()> {
+        self.register_raw_input(usage_page, usage, mode)
+    }
+
+    fn register_raw_input(
+        &self,
+        usage_page: u16,
+        usage: u16,
+        mode: Option<RawInputMode>,
+    ) -> Result<()> {
+        let (flags, target) = match mode {
+            // Removing must not name a window
+            None => (RIDEV_REMOVE, HWND::default()),
+            Some(RawInputMode::Foreground) => (RIDEV_DEVNOTIFY, self.hwnd()),
+            Some(RawInputMode::Background) => (RIDEV_INPUTSINK | RIDEV_DEVNOTIFY, self.hwnd()),
+        };
+        let device = RAWINPUTDEVICE {
+            usUsagePage: usage_page,
+            usUsage: usage,
+            dwFlags: flags,
+            hwndTarget: target,
+        };
+        unsafe { RegisterRawInputDevices(&[device], mem::size_of::<RAWINPUTDEVICE>() as u32)? };
+        Ok(())
+    }
+}
+
+/// Reads the input of a WM_INPUT message, HID devices may pack several
+/// reports in one.
+pub(crate) fn raw_input_events(handle: HRAWINPUT) -> Vec<Event> {
+    let header_size = mem::size_of::<RAWINPUTHEADER>() as u32;
+    let mut size = 0;
+    unsafe { GetRawInputData(handle, RID_INPUT, None, &mut size, header_size) };
+    // Keep the buffer aligned for RAWINPUT, HID reports make it longer
+    let mut buffer = vec![0u64; (size as usize).max(mem::size_of::<RAWINPUT>()).div_ceil(8)];
+    let read = unsafe {
+        GetRawInputData(
+            handle,
+            RID_INPUT,
+            Some(buffer.as_mut_ptr() as *mut c_void),
+            &mut size,
+            header_size,
+        )
+    };
+    if read == u32::MAX || read == 0 {
+        return Vec::new();
+    }
+    let input = unsafe { &*(buffer.as_ptr() as *const RAWINPUT) };
+
+    let events = if input.header.dwType == RIM_TYPEKEYBOARD.0 {
+        raw_key(unsafe { &input.data.keyboard })
+            .map(|(physical_key, state)| DeviceEvent::Key {
+                physical_key,
+                state,
+            })
+            .into_iter()
+            .collect()
+    } else if input.header.dwType == RIM_TYPEMOUSE.0 {
+        let mouse = unsafe { &input.data.mouse };
+        // Tablets and remote desktop sessions report absolute positions
+        if mouse.usFlags.0 & MOUSE_MOVE_ABSOLUTE.0 != 0 || (mouse.lLastX, mouse.lLastY) == (0, 0) {
+            return Vec::new();
+        }
+        vec![DeviceEvent::MouseMotion {
+            dx: mouse.lLastX,
+            dy: mouse.lLastY,
+        }]
+    } else if input.header.dwType == RIM_TYPEHID.0 {
+        let hid = unsafe { &input.data.hid };
+        let bytes = unsafe { slice::from_raw_parts(buffer.as_ptr() as *const u8, read as usize) };
+        let offset = hid.bRawData.as_ptr() as usize - buffer.as_ptr() as usize;
+        hid_reports(&bytes[offset..], hid.dwSizeHid, hid.dwCount)
+            .map(|report| DeviceEvent::HidReport(report.to_vec()))
+            .collect()
+    } else {
+        Vec::new()
+    };
+    events
+        .into_iter()
+        .map(|event| Event::DeviceEvent {
+            device_id: DeviceId::from(input.header.hDevice),
+            event,
+        })
+        .collect()
+}
+
+/// Reads a WM_INPUT_DEVICE_CHANGE message.
+pub(crate) fn device_change_event(w_param: WPARAM, l_param: LPARAM) -> Option<Event> {
+    let handle = HANDLE(l_param.0 as *mut _);
+    let event = match w_param.0 as u32 {
+        GIDC_ARRIVAL => DeviceEvent::Added(device_kind(handle)?),
+        GIDC_REMOVAL => DeviceEvent::Removed,
+        _ => return None,
+    };
+    Some(Event::DeviceEvent {
+        device_id: DeviceId::from(handle),
+        event,
+    })
+}
+
+fn device_kind(handle: HANDLE) -> Option<DeviceKind> {
+    let mut info = RID_DEVICE_INFO {
+        cbSize: mem::size_of::<RID_DEVICE_INFO>() as u32,
+        ..Default::default()
+    };
+    let mut size = info.cbSize;
+    let read = unsafe {
+        GetRawInputDeviceInfoW(
+            handle,
+            RIDI_DEVICEINFO,
+            Some(&mut info as *mut _ as *mut c_void),
+            &mut size,
+        )
+    };
+    if read == u32::MAX || read == 0 {
+        return None;
+    }
+    Some(match info.dwType {
+        RIM_TYPEMOUSE => DeviceKind::Mouse,
+        RIM_TYPEKEYBOARD => DeviceKind::Keyboard,
+        _ => {
+            let hid = unsafe { &info.Anonymous.hid };
+            DeviceKind::Hid {
+                vendor_id: hid.dwVendorId,
+                product_id: hid.dwProductId,
+                usage_page: hid.usUsagePage,
+                usage: hid.usUsage,
+            }
+        }
+    })
+}
+
+/// Splits the data of a RAWHID into its reports, ignoring any that were
+/// cut off.
+fn hid_reports(data: &[u8], size: u32, count: u32) -> impl Iterator<Item = &[u8]> {
+    let length = (size as usize * count as usize).min(data.len());
+    data[..length].chunks_exact((size as usize).max(1))
+}
+
+fn raw_key(keyboard: &RAWKEYBOARD) -> Option<(KeyCode, ElementState)> {
+    let flags = keyboard.Flags as u32;
+    // Pause sends an E1 prefixed Ctrl before its own scancode
+    if flags & RI_KEY_E1 != 0 || keyboard.MakeCode == 0xff {
+        return None;
+    }
+    // Raw input doesn't flag NumLock as extended, unlike key messages
+    let physical_key = match keyboard.VKey {
+        key if key == VK_NUMLOCK.0 => KeyCode::NumLock,
+        key if key == VK_PAUSE.0 => KeyCode::Pause,
+        _ => {
+            let extended = if flags & RI_KEY_E0 != 0 { 0x100 } else { 0 };
+            KeyCode::from_scancode(keyboard.MakeCode | extended)
+        }
+    };
+    let state = if flags & RI_KEY_BREAK != 0 {
+        ElementState::Released
+    } else {
+        ElementState::Pressed
+    };
+    Some((physical_key, state))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn translates_raw_keys() {
+        let key = |make_code, flags: u32, virtual_key: u16| RAWKEYBOARD {
+            MakeCode: make_code,
+            Flags: flags as u16,
+            VKey: virtual_key,
+            ..Default::default()
+        };
+        assert_eq!(
+            raw_key(&key(0x1d, RI_KEY_E0 | RI_KEY_BREAK, 0x11)),
+            Some((KeyCode::ControlRight, ElementState::Released))
+        );
+        assert_eq!(
+            raw_key(&key(0x45, 0, VK_NUMLOCK.0)),
+            Some((KeyCode::NumLock, ElementState::Pressed))
+        );
+        assert_eq!(raw_key(&key(0x1d, RI_KEY_E1, VK_PAUSE.0)), None);
+    }
+
+    #[test]
+    fn splits_hid_reports() {
+        let data = [1, 2, 3, 4, 5, 6, 7];
+        assert_eq!(
+            hid_reports(&data, 3, 2).collect::<Vec<_>>(),
+            [&[1, 2, 3], &[4, 5, 6]]
+        );
+        assert_eq!(hid_reports(&data, 4, 2).count(), 1);
+    }
+}
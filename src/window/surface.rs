@@ -0,0 +1,101 @@
+use std::{ffi::c_void, mem};
+
+use anyhow::{bail, Result};
+use windows::Win32::Graphics::Gdi::{
+    GetDC, ReleaseDC, SetDIBitsToDevice, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS,
+};
+
+use super::{icon::rgba_to_bgra, WindowsWindow};
+
+/// Pixels drawn on the CPU and copied to a window, for simple applications
+/// that need no GPU stack. Draw into [`Surface::buffer_mut`] and
+/// [`Surface::present`] it on
+/// [`WindowEvent::RedrawRequested`](crate::event::WindowEvent::RedrawRequested).
+#[derive(Debug)]
+pub struct Surface {
+    window: WindowsWindow,
+    width: u32,
+    height: u32,
+    rgba: Vec<u8>,
+}
+
+impl Surface {
+    pub fn new(window: WindowsWindow) -> Self {
+        Self {
+            window,
+            width: 0,
+            height: 0,
+            rgba: Vec::new(),
+        }
+    }
+
+    /// Size of the buffer in pixels, the client area's as of the last
+    /// [`Surface::buffer_mut`].
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// The pixels to draw, as straight RGBA rows from the top. The buffer
+    /// follows the size of the client area: after the window resized, the
+    /// pixels are cleared to transparent black.
+    pub fn buffer_mut(&mut self) -> Result<&mut [u8]> {
+        let (width, height) = self.window.inner_size()?;
+        if (width, height) != (self.width, self.height) {
+            self.width = width;
+            self.height = height;
+            self.rgba.clear();
+            self.rgba.resize(width as usize * height as usize * 4, 0);
+        }
+        Ok(&mut self.rgba)
+    }
+
+    /// Copies the buffer to the top left of the client area. Alpha is
+    /// ignored.
+    pub fn present(&mut self) -> Result<()> {
+        if self.rgba.is_empty() {
+            // Minimized, or nothing was drawn yet
+            return Ok(());
+        }
+        let bgra = rgba_to_bgra(&self.rgba, self.width, self.height)?;
+        let info = BITMAPINFO {
+            bmiHeader: BITMAPINFOHEADER {
+                biSize: mem::size_of::<BITMAPINFOHEADER>() as u32,
+                biWidth: self.width as i32,
+                // Top-down rows
+                biHeight: -(self.height as i32),
+                biPlanes: 1,
+                biBitCount: 32,
+                biCompression: BI_RGB.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let hwnd = self.window.hwnd();
+        let copied = unsafe {
+            let hdc = GetDC(hwnd);
+            if hdc.is_invalid() {
+                bail!("Could not get the device context of the window");
+            }
+            let copied = SetDIBitsToDevice(
+                hdc,
+                0,
+                0,
+                self.width,
+                self.height,
+                0,
+                0,
+                0,
+                self.height,
+                bgra.as_ptr() as *const c_void,
+                &info,
+                DIB_RGB_COLORS,
+            );
+            ReleaseDC(hwnd, hdc);
+            copied
+        };
+        if copied == 0 {
+            bail!("Could not copy the pixels to the window");
+        }
+        Ok(())
+    }
+}
@@ -1,282 +1,2181 @@
-use std::ffi::{c_uint, c_void};
-
-use anyhow::{bail, Result};
-use windows::{
-    core::{w, PCWSTR},
-    Win32::{
-        Foundation::{
-            GetLastError, SetLastError, HINSTANCE, HMODULE, HWND, LPARAM, LRESULT, RECT,
-            WIN32_ERROR, WPARAM,
-        },
-        Graphics::Gdi::{
-            BeginPaint, EndPaint, FillRect, UpdateWindow, COLOR_WINDOW, HBRUSH, HDC, PAINTSTRUCT,
-            SYS_COLOR_INDEX,
-        },
-        System::SystemServices::IMAGE_DOS_HEADER,
-        UI::WindowsAndMessaging::*,
-    },
-};
-
-use crate::utils::strings::str_to_wstr;
-
-pub struct WindowsWindow {}
-
-impl WindowsWindow {
-    pub fn new(title: &str, width: Option<i32>, height: Option<i32>) -> Result<Self> {
-        // Get Application Instance Handle
-        let h_instance = get_instance_handle();
-
-        let window_class = w!("window");
-
-        let window_title = PCWSTR(str_to_wstr(title).as_ptr());
-
-        Self::register_class(h_instance, window_class)?;
-        Self::init_instance(
-            h_instance,
-            window_class,
-            window_title,
-            SW_SHOW,
-            width,
-            height,
-        );
-        Ok(Self {})
-    }
-
-    fn register_class(h_instance: HMODULE, class_name: PCWSTR) -> Result<()> {
-        // Crete empty WNDCLASSW (Wide)
-        let mut wc = WNDCLASSW::default();
-
-        // Fill minimum requirements
-        wc.style = CS_HREDRAW | CS_VREDRAW;
-        wc.lpfnWndProc = Some(Self::window_procedure);
-        wc.hInstance = h_instance.into();
-        wc.hCursor = load_default_cursor(IDC_ARROW)?;
-        wc.lpszClassName = class_name;
-
-        // Register Window Class (WNDCLASSW)
-        let atom = unsafe { RegisterClassW(&wc) };
-        if atom == 0 {
-            let last_error = unsafe { GetLastError() };
-            bail!(
-                "Could not register the window class, error code: {:?}",
-                last_error
-            );
-        }
-
-        Ok(())
-    }
-
-    fn init_instance(
-        h_instance: HMODULE,
-        class_name: PCWSTR,
-        window_title: PCWSTR,
-        n_cmd_show: SHOW_WINDOW_CMD,
-        width: Option<i32>,
-        height: Option<i32>,
-    ) {
-        // Prepare app data
-        let lparam: *mut i32 = Box::leak(Box::new(5_i32));
-
-        // Create window of class wc and get Handle
-        let hwnd = unsafe {
-            CreateWindowExW(
-                WS_EX_RIGHTSCROLLBAR,
-                class_name,
-                window_title,
-                WS_OVERLAPPEDWINDOW,
-                CW_USEDEFAULT,
-                0,
-                width.unwrap_or(CW_USEDEFAULT),
-                height.unwrap_or(0),
-                HWND::default(),
-                HMENU::default(),
-                h_instance,
-                Some(lparam.cast()),
-            )
-            .unwrap()
-        };
-
-        // Show created window
-        let code = unsafe { ShowWindow(hwnd, n_cmd_show) };
-        if code.0 != 0 {
-            let last_error = unsafe { GetLastError() };
-            panic!("Could not create window, error code: {:?}", last_error);
-        }
-        unsafe {
-            UpdateWindow(hwnd).unwrap();
-        };
-    }
-
-    pub unsafe extern "system" fn window_procedure(
-        hwnd: HWND,
-        msg: c_uint,
-        w_param: WPARAM,
-        l_param: LPARAM,
-    ) -> LRESULT {
-        match msg {
-            WM_NCCREATE => {
-                let createstruct: *mut CREATESTRUCTW = l_param.0 as *mut _;
-                if createstruct.is_null() {
-                    return LRESULT(0);
-                }
-                //Set Window Title
-                SetWindowTextW(hwnd, (*createstruct).lpszName).unwrap();
-
-                let ptr: *mut i32 = (*createstruct).lpCreateParams.cast();
-                return LRESULT(set_window_userdata::<i32>(hwnd, ptr).is_ok() as isize);
-            }
-            //WM_CREATE => (),
-            WM_CLOSE => {
-                let _ = DestroyWindow(hwnd);
-            }
-            WM_DESTROY => {
-                match get_window_userdata::<i32>(hwnd) {
-                    Ok(ptr) if !ptr.is_null() => {
-                        let _ = Box::from_raw(ptr);
-                    }
-                    Ok(_) => (),
-                    Err(e) => {
-                        println!(
-                            "Error while getting the userdata ptr to clean it up: {:?}",
-                            e
-                        );
-                    }
-                }
-                post_quit_message(0);
-            }
-            WM_PAINT => {
-                do_some_painting(hwnd, |hdc, _erase_bg, target_rect| {
-                    let _ = fill_rect_with_sys_color(hdc, &target_rect, COLOR_WINDOW);
-                    Ok(())
-                })
-                .unwrap_or_else(|e| println!("Error during painting: {:?}", e));
-            }
-            _ => return DefWindowProcW(hwnd, msg, w_param, l_param),
-        }
-        LRESULT(0)
-    }
-
-    pub fn window_loop(&self) {
-        loop {
-            match get_next_message() {
-                Ok(msg) => {
-                    if msg.message == WM_QUIT {
-                        std::process::exit(msg.wParam.0 as i32);
-                    }
-                    let _ = translte_message(&msg);
-                    unsafe {
-                        DispatchMessageW(&msg);
-                    }
-                }
-                Err(e) => panic!("Failed getting next message: {}", e),
-            }
-        }
-    }
-}
-
-pub fn get_instance_handle() -> HMODULE {
-    extern "C" {
-        static __ImageBase: IMAGE_DOS_HEADER;
-    }
-
-    HMODULE(unsafe { &__ImageBase as *const _ as *mut c_void })
-}
-
-pub fn load_default_cursor(cursor: PCWSTR) -> Result<HCURSOR> {
-    let hcursor = unsafe { LoadCursorW(HINSTANCE::default(), cursor).unwrap() };
-    if hcursor.is_invalid() {
-        bail!("Failed to load predefined cursor");
-    } else {
-        Ok(hcursor)
-    }
-}
-
-pub fn get_next_message() -> Result<MSG> {
-    let mut msg = MSG::default();
-    let output = unsafe { GetMessageW(&mut msg, HWND::default(), 0, 0) };
-    if output.0 >= 0 {
-        Ok(msg)
-    } else {
-        bail!("Failed getting next message")
-    }
-}
-
-pub fn translte_message(msg: &MSG) -> Result<bool> {
-    let res = unsafe { TranslateMessage(msg) };
-    match res.ok() {
-        Ok(_) => Ok(0 != res.0),
-        Err(err) => Err(err.into()),
-    }
-}
-
-pub unsafe fn set_window_userdata<T>(hwnd: HWND, ptr: *mut T) -> Result<*mut T, WIN32_ERROR> {
-    SetLastError(WIN32_ERROR(0));
-    let out = SetWindowLongPtrW(hwnd, GWLP_USERDATA, ptr as isize);
-    if out == 0 {
-        let last_error = GetLastError();
-        if last_error.0 != 0 {
-            Err(last_error)
-        } else {
-            Ok(out as *mut T)
-        }
-    } else {
-        Ok(out as *mut T)
-    }
-}
-
-pub unsafe fn get_window_userdata<T>(hwnd: HWND) -> Result<*mut T, WIN32_ERROR> {
-    SetLastError(WIN32_ERROR(0));
-    let out = GetWindowLongPtrW(hwnd, GWLP_USERDATA);
-    if out == 0 {
-        let last_error = GetLastError();
-        if last_error.0 != 0 {
-            Err(last_error)
-        } else {
-            Ok(out as *mut T)
-        }
-    } else {
-        Ok(out as *mut T)
-    }
-}
-
-pub fn post_quit_message(exit_code: i32) {
-    unsafe {
-        PostQuitMessage(exit_code);
-    }
-}
-
-pub unsafe fn begin_paint(hwnd: HWND) -> Result<(HDC, PAINTSTRUCT), WIN32_ERROR> {
-    let mut ps = PAINTSTRUCT::default();
-    let hdc = BeginPaint(hwnd, &mut ps);
-    if hdc.is_invalid() {
-        Err(GetLastError())
-    } else {
-        Ok((hdc, ps))
-    }
-}
-
-pub unsafe fn fill_rect_with_sys_color(
-    hdc: HDC,
-    rect: &RECT,
-    color: SYS_COLOR_INDEX,
-) -> Result<(), ()> {
-    if FillRect(hdc, rect, (HBRUSH)((color.0 + 1) as *mut c_void)) != 0 {
-        Ok(())
-    } else {
-        Err(())
-    }
-}
-
-pub unsafe fn end_paint(hwnd: HWND, ps: &PAINTSTRUCT) {
-    EndPaint(hwnd, ps).unwrap();
-}
-
-pub unsafe fn do_some_painting<F, T>(hwnd: HWND, f: F) -> Result<T, WIN32_ERROR>
-where
-    F: FnOnce(HDC, bool, RECT) -> Result<T, WIN32_ERROR>,
-{
-    let (hdc, ps) = begin_paint(hwnd)?;
-    let output = f(hdc, ps.fErase.as_bool(), ps.rcPaint);
-    end_paint(hwnd, &ps);
-    output
-}
+use std::{
+    cell::{Cell, RefCell},
+    ffi::{c_uint, c_void},
+    rc::Weak,
+    time::Duration,
+};
+
+use anyhow::{bail, Result};
+use windows::{
+    core::PCWSTR,
+    Win32::{
+        Foundation::{
+            GetLastError, SetLastError, COLORREF, ERROR_CLASS_ALREADY_EXISTS, HINSTANCE, HMODULE,
+            HWND, LPARAM, LRESULT, POINT, RECT, WIN32_ERROR, WPARAM,
+        },
+        Graphics::{
+            Dwm::{DwmEnableBlurBehindWindow, DWM_BB_BLURREGION, DWM_BB_ENABLE, DWM_BLURBEHIND},
+            Gdi::{
+                BeginPaint, ClientToScreen, CombineRgn, CreateRectRgn, CreateRectRgnIndirect,
+                DeleteObject, EndPaint, InvalidateRect, MapWindowPoints, ScreenToClient,
+                SetWindowRgn, UpdateWindow, HBRUSH, HDC, HRGN, PAINTSTRUCT, RGN_ERROR, RGN_OR,
+                SYS_COLOR_INDEX,
+            },
+        },
+        System::SystemServices::IMAGE_DOS_HEADER,
+        UI::{
+            Controls::WM_MOUSELEAVE,
+            HiDpi::GetDpiForWindow,
+            Input::{
+                KeyboardAndMouse::{
+                    EnableWindow, GetCapture, GetFocus, IsWindowEnabled, ReleaseCapture,
+                    SetCapture, HKL,
+                },
+                Touch::GESTUREINFO,
+                HRAWINPUT,
+            },
+            WindowsAndMessaging::*,
+        },
+    },
+};
+
+#[cfg(feature = "gl")]
+use windows::Win32::Graphics::OpenGL::HGLRC;
+
+use crate::{
+    event::{ElementState, Event, MouseButton, ScrollDelta, Theme, WindowEvent},
+    event_loop::windows::{catch_panic, dispatch_event, modal_loop_tick},
+    keyboard::{keyboard_snapshot, KeyCode, KeyboardLayout, ModifiersState},
+    utils::{strings::str_to_wstr, trace::log_error},
+};
+
+use super::{
+    builder::frame_style,
+    cursor::{Cursor, CursorGrabMode},
+    dwm::system_theme,
+    raw_input::{device_change_event, raw_input_events},
+    Icon, MonitorHandle, Painter, WindowBuilder, WindowClass,
+};
+
+/// Identifies a window across events, stable for the window's lifetime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WindowId(isize);
+
+impl WindowId {
+    pub(crate) fn hwnd(self) -> HWND {
+        HWND(self.0 as *mut _)
+    }
+}
+
+impl From<HWND> for WindowId {
+    fn from(hwnd: HWND) -> Self {
+        Self(hwnd.0 as isize)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WindowsWindow {
+    hwnd: HWND,
+}
+
+/// Fullscreen mode of a window, see [`WindowsWindow::set_fullscreen`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fullscreen {
+    /// Covers the given monitor, or the window's current one, with an
+    /// undecorated window.
+    Borderless(Option<MonitorHandle>),
+}
+
+/// How insistently [`WindowsWindow::request_user_attention`] asks for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserAttentionType {
+    /// Flashes the caption and taskbar button until the window comes to the
+    /// foreground.
+    Critical,
+    /// Flashes the taskbar button until the window comes to the foreground.
+    Informational,
+}
+
+/// An edge or corner of a window's frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResizeDirection {
+    East,
+    North,
+    NorthEast,
+    NorthWest,
+    South,
+    SouthEast,
+    SouthWest,
+    West,
+}
+
+impl ResizeDirection {
+    fn hit_test_code(self) -> u32 {
+        match self {
+            ResizeDirection::East => HTRIGHT,
+            ResizeDirection::North => HTTOP,
+            ResizeDirection::NorthEast => HTTOPRIGHT,
+            ResizeDirection::NorthWest => HTTOPLEFT,
+            ResizeDirection::South => HTBOTTOM,
+            ResizeDirection::SouthEast => HTBOTTOMRIGHT,
+            ResizeDirection::SouthWest => HTBOTTOMLEFT,
+            ResizeDirection::West => HTLEFT,
+        }
+    }
+}
+
+/// Transition played by [`WindowsWindow::show_animated`] and
+/// [`WindowsWindow::hide_animated`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Animation {
+    /// Rolls the window in or out, moving in the given direction.
+    Slide(SlideDirection),
+    /// Fades the window in or out. Top-level windows only.
+    Fade,
+    /// Grows the window from its center, or collapses it into it.
+    Expand,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SlideDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl Animation {
+    fn flags(self) -> ANIMATE_WINDOW_FLAGS {
+        match self {
+            Animation::Slide(SlideDirection::Left) => AW_SLIDE | AW_HOR_NEGATIVE,
+            Animation::Slide(SlideDirection::Right) => AW_SLIDE | AW_HOR_POSITIVE,
+            Animation::Slide(SlideDirection::Up) => AW_SLIDE | AW_VER_NEGATIVE,
+            Animation::Slide(SlideDirection::Down) => AW_SLIDE | AW_VER_POSITIVE,
+            Animation::Fade => AW_BLEND,
+            Animation::Expand => AW_CENTER,
+        }
+    }
+}
+
+/// What a fullscreen window goes back to.
+#[derive(Clone, Copy)]
+struct Windowed {
+    style: WINDOW_STYLE,
+    placement: WINDOWPLACEMENT,
+}
+
+/// Destroys a window when dropped, forgotten once it's set up.
+struct DestroyGuard(HWND);
+
+impl Drop for DestroyGuard {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = DestroyWindow(self.0);
+        }
+    }
+}
+
+/// Sees every raw message of a window before paneless handles it. Returning
+/// `Some` consumes the message with that result, `None` passes it through.
+pub type MsgHook = Box<dyn FnMut(HWND, u32, WPARAM, LPARAM) -> Option<LRESULT>>;
+
+/// What part of the window a point belongs to, as decided by a
+/// [`HitTestFn`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HitTest {
+    /// Regular client area, delivering mouse events.
+    Client,
+    /// Moves the window when dragged, maximizes it on double click, snaps
+    /// to screen edges and opens the system menu on right click.
+    Caption,
+    SystemMenu,
+    MinButton,
+    MaxButton,
+    CloseButton,
+    ResizeBorder(ResizeDirection),
+    /// Passes the mouse through to the window underneath.
+    Transparent,
+}
+
+impl HitTest {
+    fn code(self) -> isize {
+        let code = match self {
+            HitTest::Client => HTCLIENT,
+            HitTest::Caption => HTCAPTION,
+            HitTest::SystemMenu => HTSYSMENU,
+            HitTest::MinButton => HTMINBUTTON,
+            HitTest::MaxButton => HTMAXBUTTON,
+            HitTest::CloseButton => HTCLOSE,
+            HitTest::ResizeBorder(direction) => direction.hit_test_code(),
+            HitTest::Transparent => return HTTRANSPARENT as isize,
+        };
+        code as isize
+    }
+}
+
+/// Decides which part of the window a point in client coordinates is, see
+/// [`WindowsWindow::with_hit_test`]. `None` keeps the system's answer.
+pub type HitTestFn = Box<dyn FnMut(i32, i32) -> Option<HitTest>>;
+
+/// Swapchain or render target following the client size of its window.
+pub(crate) trait ResizeTarget {
+    fn resize(&self, width: u32, height: u32);
+}
+
+/// Per-window state, owned by the window through `GWLP_USERDATA` and freed
+/// on `WM_NCDESTROY`.
+#[derive(Default)]
+pub(crate) struct WindowState {
+    // Taken while it runs, so re-entrant messages skip it.
+    msg_hook: Cell<Option<MsgHook>>,
+    hit_test: Cell<Option<HitTestFn>>,
+    close_vetoed: Cell<bool>,
+    // The background is left to the compositor.
+    transparent: Cell<bool>,
+    // Frame attributes, the styles alone can't tell them apart once
+    // decorations are off.
+    resizable: Cell<bool>,
+    decorations: Cell<bool>,
+    fullscreen: Cell<Option<(Fullscreen, Windowed)>>,
+    // Kept alive while the window shows it.
+    icon: RefCell<Option<Icon>>,
+    // Resized with the parent's client area.
+    fill_parent: Cell<bool>,
+    // Owner disabled by show_modal.
+    modal_owner: Cell<Option<HWND>>,
+    // Theme forced by the application, and the system one last seen.
+    pub(crate) theme: Cell<Option<Theme>>,
+    pub(crate) system_theme: Cell<Option<Theme>>,
+    // wParam of the last WM_SIZE, to report state changes only.
+    size_state: Cell<u32>,
+    min_inner_size: Cell<Option<(u32, u32)>>,
+    max_inner_size: Cell<Option<(u32, u32)>>,
+    aspect_ratio: Cell<Option<(u32, u32)>>,
+    // Held keys don't send KeyboardInput again.
+    suppress_key_repeat: Cell<bool>,
+    // Taken again whenever the window gets the focus back.
+    pub(crate) cursor_grab: Cell<CursorGrabMode>,
+    // Kept alive while the window shows it.
+    pub(crate) cursor: RefCell<Cursor>,
+    pub(crate) cursor_hidden: Cell<bool>,
+    // Over the client area, a WM_MOUSELEAVE is pending.
+    pub(crate) cursor_inside: Cell<bool>,
+    // Last modifiers reported with ModifiersChanged.
+    modifiers: Cell<ModifiersState>,
+    // Composition reported as Ime events, and where the IME goes.
+    pub(crate) ime_events: Cell<bool>,
+    pub(crate) ime_cursor_area: Cell<Option<RECT>>,
+    // First half of a surrogate pair sent through WM_CHAR.
+    high_surrogate: Cell<Option<u16>>,
+    // Gestures reported as Gesture events, and the last message of the
+    // current one.
+    pub(crate) gestures: Cell<bool>,
+    pub(crate) last_gesture: Cell<GESTUREINFO>,
+    // Sticky Keys and the like don't interrupt the window in fullscreen.
+    pub(crate) suppress_accessibility_shortcuts: Cell<bool>,
+    // Deleted with the window, see GlContext.
+    #[cfg(feature = "gl")]
+    pub(crate) gl_context: Cell<Option<HGLRC>>,
+    // DC, erase flag and dirty area of the open paint cycle.
+    pub(crate) painting: Cell<Option<(HDC, bool, RECT)>>,
+    // Resized before Resized is dispatched, dropped ones are pruned then.
+    resize_targets: RefCell<Vec<Weak<dyn ResizeTarget>>>,
+}
+
+impl WindowState {
+    fn call_msg_hook(
+        &self,
+        hwnd: HWND,
+        msg: u32,
+        w_param: WPARAM,
+        l_param: LPARAM,
+    ) -> Option<LRESULT> {
+        let mut hook = self.msg_hook.take()?;
+        // Passes the message through if the hook panicked
+        let result = catch_panic(|| hook(hwnd, msg, w_param, l_param)).flatten();
+        // Keep a hook installed by the hook itself
+        let replaced = self.msg_hook.take();
+        self.msg_hook.set(replaced.or(Some(hook)));
+        result
+    }
+
+    fn has_hit_test(&self) -> bool {
+        let hit_test = self.hit_test.take();
+        let installed = hit_test.is_some();
+        self.hit_test.set(hit_test);
+        installed
+    }
+
+    fn call_hit_test(&self, x: i32, y: i32) -> Option<HitTest> {
+        let mut hit_test = self.hit_test.take()?;
+        // Falls back to the default hit test if it panicked
+        let result = catch_panic(|| hit_test(x, y)).flatten();
+        let replaced = self.hit_test.take();
+        self.hit_test.set(replaced.or(Some(hit_test)));
+        result
+    }
+}
+
+/// Gets the state of a window created by paneless, `None` until `WM_NCCREATE`
+/// stored it.
+pub(crate) unsafe fn window_state<'a>(hwnd: HWND) -> Option<&'a WindowState> {
+    get_window_userdata::<WindowState>(hwnd)
+        .ok()
+        .and_then(|ptr| ptr.as_ref())
+}
+
+/// Ticks while the window is being moved or resized interactively.
+const MODAL_LOOP_TIMER_ID: usize = 0x5041_4e45;
+
+thread_local! {
+    // Windows of this thread that haven't been destroyed yet.
+    static LIVE_WINDOWS: Cell<usize> = const { Cell::new(0) };
+}
+
+impl WindowsWindow {
+    /// Creates a window from the attributes of `builder`, already validated.
+    pub(crate) fn create(builder: &WindowBuilder) -> Result<Self> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("create_window", title = builder.title).entered();
+
+        // Get Application Instance Handle
+        let h_instance = get_instance_handle();
+
+        let window_title = str_to_wstr(&builder.title);
+
+        let window_class = Self::register_class(h_instance, &builder.class)?;
+        let hwnd = Self::init_instance(
+            h_instance,
+            PCWSTR(window_class.as_ptr()),
+            PCWSTR(window_title.as_ptr()),
+            builder,
+        )?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(hwnd = hwnd.0 as isize, "window created");
+        Ok(Self { hwnd })
+    }
+
+    /// Creates a message-only window: it is never shown, can't be enumerated
+    /// and receives no broadcasts, but still gets posted and sent messages,
+    /// timers and `WM_COPYDATA`. Lets services and tests drive the event loop
+    /// without anything appearing on screen.
+    pub fn new_message_only() -> Result<Self> {
+        let h_instance = get_instance_handle();
+        let window_class = Self::register_class(h_instance, &WindowClass::default())?;
+
+        // Prepare window state, reclaimed on WM_NCDESTROY
+        let lparam: *mut WindowState = Box::into_raw(Box::default());
+        let hwnd = unsafe {
+            CreateWindowExW(
+                WINDOW_EX_STYLE::default(),
+                PCWSTR(window_class.as_ptr()),
+                PCWSTR::null(),
+                WINDOW_STYLE::default(),
+                0,
+                0,
+                0,
+                0,
+                HWND_MESSAGE,
+                HMENU::default(),
+                h_instance,
+                Some(lparam.cast()),
+            )?
+        };
+        Ok(Self { hwnd })
+    }
+
+    pub(crate) fn from_hwnd(hwnd: HWND) -> Self {
+        Self { hwnd }
+    }
+
+    pub fn hwnd(&self) -> HWND {
+        self.hwnd
+    }
+
+    pub fn id(&self) -> WindowId {
+        WindowId::from(self.hwnd)
+    }
+
+    /// Installs a hook that sees the raw messages of this window ahead of the
+    /// crate's own handling, for interop with libraries that need them (Dear
+    /// ImGui, egui, accessibility layers, ...). Replaces any previous hook.
+    ///
+    /// A panic in the hook resumes from the event loop, like one in the
+    /// event handler.
+    pub fn with_msg_hook<F>(self, hook: F) -> Self
+    where
+        F: FnMut(HWND, u32, WPARAM, LPARAM) -> Option<LRESULT> + 'static,
+    {
+        if let Some(state) = unsafe { window_state(self.hwnd) } {
+            state.msg_hook.set(Some(Box::new(hook)));
+        }
+        self
+    }
+
+    /// Lets `hit_test` decide which points of the window act as caption,
+    /// caption buttons or resize borders, so a custom-drawn title bar still
+    /// behaves like a native one. Replaces any previous hit test.
+    ///
+    /// Returning [`HitTest::MaxButton`] over a custom maximize button shows
+    /// the Windows 11 snap layouts flyout when hovering it. Custom caption
+    /// buttons deliver [`WindowEvent::CursorMoved`] and
+    /// [`WindowEvent::MouseInput`] for hover and press feedback, and act when
+    /// clicked.
+    ///
+    /// It runs inside the window procedure on every mouse move. A panic in
+    /// it resumes from the event loop, like one in the event handler.
+    pub fn with_hit_test<F>(self, hit_test: F) -> Self
+    where
+        F: FnMut(i32, i32) -> Option<HitTest> + 'static,
+    {
+        if let Some(state) = unsafe { window_state(self.hwnd) } {
+            state.hit_test.set(Some(Box::new(hit_test)));
+        }
+        self
+    }
+
+    pub fn set_title(&self, title: &str) -> Result<()> {
+        let title = str_to_wstr(title);
+        unsafe { SetWindowTextW(self.hwnd, PCWSTR(title.as_ptr()))? };
+        Ok(())
+    }
+
+    pub fn title(&self) -> String {
+        let len = unsafe { GetWindowTextLengthW(self.hwnd) };
+        // Room for the terminating NUL
+        let mut buffer = vec![0u16; len as usize + 1];
+        let copied = unsafe { GetWindowTextW(self.hwnd, &mut buffer) };
+        String::from_utf16_lossy(&buffer[..copied as usize])
+    }
+
+    /// Ratio of the window's DPI to the 96 DPI of a 100% scale, to convert
+    /// the physical pixels of sizes and positions to logical ones. Always 1
+    /// unless the process declares itself DPI aware, Windows then scales
+    /// the window itself.
+    pub fn scale_factor(&self) -> f64 {
+        match unsafe { GetDpiForWindow(self.hwnd) } {
+            0 => 1.0,
+            dpi => dpi as f64 / USER_DEFAULT_SCREEN_DPI as f64,
+        }
+    }
+
+    /// Size of the client area.
+    pub fn inner_size(&self) -> Result<(u32, u32)> {
+        let mut rect = RECT::default();
+        unsafe { GetClientRect(self.hwnd, &mut rect)? };
+        Ok(rect_size(&rect))
+    }
+
+    /// Size of the window including its frame and title bar.
+    pub fn outer_size(&self) -> Result<(u32, u32)> {
+        let mut rect = RECT::default();
+        unsafe { GetWindowRect(self.hwnd, &mut rect)? };
+        Ok(rect_size(&rect))
+    }
+
+    /// Resizes the window so its client area is `width` by `height`.
+    pub fn set_inner_size(&self, width: u32, height: u32) -> Result<()> {
+        let (width, height) = outer_size_for(width, height, self.style(), self.ex_style())?;
+        unsafe {
+            SetWindowPos(
+                self.hwnd,
+                HWND::default(),
+                0,
+                0,
+                width,
+                height,
+                SWP_NOMOVE | SWP_NOZORDER | SWP_NOACTIVATE,
+            )?
+        };
+        Ok(())
+    }
+
+    /// Keeps the user from resizing the client area below `size`, `None`
+    /// lifts the limit.
+    pub fn set_min_inner_size(&self, size: Option<(u32, u32)>) -> Result<()> {
+        let Some(state) = (unsafe { window_state(self.hwnd) }) else {
+            bail!("Not a paneless window");
+        };
+        state.min_inner_size.set(size);
+        self.apply_size_constraints()
+    }
+
+    /// Keeps the user from resizing the client area beyond `size`, `None`
+    /// lifts the limit.
+    pub fn set_max_inner_size(&self, size: Option<(u32, u32)>) -> Result<()> {
+        let Some(state) = (unsafe { window_state(self.hwnd) }) else {
+            bail!("Not a paneless window");
+        };
+        state.max_inner_size.set(size);
+        self.apply_size_constraints()
+    }
+
+    /// Keeps the client area at `width:height` while the user drags the
+    /// frame, e.g. 16:9 for a video player. `None` lets it resize freely.
+    pub fn set_aspect_ratio(&self, ratio: Option<(u32, u32)>) -> Result<()> {
+        if ratio.is_some_and(|(width, height)| width == 0 || height == 0) {
+            bail!("Aspect ratio must not be zero");
+        }
+        let Some(state) = (unsafe { window_state(self.hwnd) }) else {
+            bail!("Not a paneless window");
+        };
+        state.aspect_ratio.set(ratio);
+        Ok(())
+    }
+
+    /// Resizes the window to its current size, which goes through
+    /// WM_GETMINMAXINFO again.
+    fn apply_size_constraints(&self) -> Result<()> {
+        let (width, height) = self.outer_size()?;
+        unsafe {
+            SetWindowPos(
+                self.hwnd,
+                HWND::default(),
+                0,
+                0,
+                width as i32,
+                height as i32,
+                SWP_NOMOVE | SWP_NOZORDER | SWP_NOACTIVATE,
+            )?
+        };
+        Ok(())
+    }
+
+    /// Position of the top-left corner of the frame, in screen coordinates.
+    pub fn outer_position(&self) -> Result<(i32, i32)> {
+        let mut rect = RECT::default();
+        unsafe { GetWindowRect(self.hwnd, &mut rect)? };
+        Ok((rect.left, rect.top))
+    }
+
+    /// Moves the top-left corner of the frame to `x`, `y` in screen
+    /// coordinates (relative to the parent's client area for child windows).
+    pub fn set_outer_position(&self, x: i32, y: i32) -> Result<()> {
+        unsafe {
+            SetWindowPos(
+                self.hwnd,
+                HWND::default(),
+                x,
+                y,
+                0,
+                0,
+                SWP_NOSIZE | SWP_NOZORDER | SWP_NOACTIVATE,
+            )?
+        };
+        Ok(())
+    }
+
+    /// Centers the window on the work area of the monitor it is on.
+    fn center(&self) -> Result<()> {
+        let work = MonitorHandle::from_window(self.hwnd).work_area()?;
+        let (width, height) = self.outer_size()?;
+        self.set_outer_position(
+            work.left + (work.right - work.left - width as i32) / 2,
+            work.top + (work.bottom - work.top - height as i32) / 2,
+        )
+    }
+
+    pub(crate) fn style(&self) -> WINDOW_STYLE {
+        WINDOW_STYLE(unsafe { GetWindowLongPtrW(self.hwnd, GWL_STYLE) } as u32)
+    }
+
+    pub(crate) fn ex_style(&self) -> WINDOW_EX_STYLE {
+        WINDOW_EX_STYLE(unsafe { GetWindowLongPtrW(self.hwnd, GWL_EXSTYLE) } as u32)
+    }
+
+    /// Replaces the window style and redraws the frame to match.
+    pub(crate) fn set_style(&self, style: WINDOW_STYLE) -> Result<()> {
+        unsafe {
+            SetWindowLongPtrW(self.hwnd, GWL_STYLE, style.0 as isize);
+        }
+        self.refresh_frame()
+    }
+
+    pub(crate) fn set_ex_style(&self, ex_style: WINDOW_EX_STYLE) -> Result<()> {
+        unsafe {
+            SetWindowLongPtrW(self.hwnd, GWL_EXSTYLE, ex_style.0 as isize);
+        }
+        self.refresh_frame()
+    }
+
+    // Cached frame data is only updated by SetWindowPos
+    fn refresh_frame(&self) -> Result<()> {
+        unsafe {
+            SetWindowPos(
+                self.hwnd,
+                HWND::default(),
+                0,
+                0,
+                0,
+                0,
+                SWP_FRAMECHANGED | SWP_NOMOVE | SWP_NOSIZE | SWP_NOZORDER | SWP_NOACTIVATE,
+            )?
+        };
+        Ok(())
+    }
+
+    /// Lets the user resize and maximize the window, or fixes its size.
+    /// Without decorations this only takes effect once they are restored.
+    pub fn set_resizable(&self, resizable: bool) -> Result<()> {
+        let Some(state) = (unsafe { window_state(self.hwnd) }) else {
+            bail!("Not a paneless window");
+        };
+        state.resizable.set(resizable);
+        self.set_style(frame_style(
+            self.style(),
+            state.decorations.get(),
+            resizable,
+        ))
+    }
+
+    pub fn is_resizable(&self) -> bool {
+        unsafe { window_state(self.hwnd) }.is_some_and(|state| state.resizable.get())
+    }
+
+    /// Shows or removes the title bar and borders. An undecorated window can
+    /// still be moved, resized and closed through this handle.
+    pub fn set_decorations(&self, decorations: bool) -> Result<()> {
+        let Some(state) = (unsafe { window_state(self.hwnd) }) else {
+            bail!("Not a paneless window");
+        };
+        state.decorations.set(decorations);
+        self.set_style(frame_style(
+            self.style(),
+            decorations,
+            state.resizable.get(),
+        ))
+    }
+
+    pub fn is_decorated(&self) -> bool {
+        unsafe { window_state(self.hwnd) }.is_some_and(|state| state.decorations.get())
+    }
+
+    /// Enters fullscreen, or goes back to the windowed style and placement
+    /// the window had before with `None`.
+    pub fn set_fullscreen(&self, fullscreen: Option<Fullscreen>) -> Result<()> {
+        let Some(state) = (unsafe { window_state(self.hwnd) }) else {
+            bail!("Not a paneless window");
+        };
+        match (fullscreen, state.fullscreen.get()) {
+            (Some(fullscreen @ Fullscreen::Borderless(monitor)), current) => {
+                let windowed = match current {
+                    Some((_, windowed)) => windowed,
+                    None => {
+                        let mut placement = WINDOWPLACEMENT {
+                            length: std::mem::size_of::<WINDOWPLACEMENT>() as u32,
+                            ..Default::default()
+                        };
+                        unsafe { GetWindowPlacement(self.hwnd, &mut placement)? };
+                        Windowed {
+                            style: self.style(),
+                            placement,
+                        }
+                    }
+                };
+                let monitor = monitor.unwrap_or_else(|| MonitorHandle::from_window(self.hwnd));
+                let bounds = monitor.bounds()?;
+                unsafe {
+                    SetWindowLongPtrW(
+                        self.hwnd,
+                        GWL_STYLE,
+                        frame_style(windowed.style, false, false).0 as isize,
+                    );
+                    SetWindowPos(
+                        self.hwnd,
+                        HWND_TOP,
+                        bounds.left,
+                        bounds.top,
+                        bounds.right - bounds.left,
+                        bounds.bottom - bounds.top,
+                        SWP_FRAMECHANGED | SWP_NOOWNERZORDER,
+                    )?;
+                }
+                state.fullscreen.set(Some((fullscreen, windowed)));
+            }
+            (None, Some((_, windowed))) => {
+                state.fullscreen.set(None);
+                unsafe {
+                    SetWindowLongPtrW(self.hwnd, GWL_STYLE, windowed.style.0 as isize);
+                    SetWindowPlacement(self.hwnd, &windowed.placement)?;
+                }
+                self.refresh_frame()?;
+            }
+            (None, None) => (),
+        }
+        self.update_accessibility_shortcuts(unsafe { GetFocus() } == self.hwnd);
+        Ok(())
+    }
+
+    pub fn fullscreen(&self) -> Option<Fullscreen> {
+        unsafe { window_state(self.hwnd) }
+            .and_then(|state| state.fullscreen.get())
+            .map(|(fullscreen, _)| fullscreen)
+    }
+
+    /// Keeps the window above all non-topmost windows, even when inactive.
+    pub fn set_always_on_top(&self, always_on_top: bool) -> Result<()> {
+        let insert_after = if always_on_top {
+            HWND_TOPMOST
+        } else {
+            HWND_NOTOPMOST
+        };
+        unsafe {
+            SetWindowPos(
+                self.hwnd,
+                insert_after,
+                0,
+                0,
+                0,
+                0,
+                SWP_NOMOVE | SWP_NOSIZE | SWP_NOACTIVATE,
+            )?
+        };
+        Ok(())
+    }
+
+    pub fn is_always_on_top(&self) -> bool {
+        self.ex_style().contains(WS_EX_TOPMOST)
+    }
+
+    /// Blends the whole window with what is behind it, from `0.0`
+    /// (invisible) to `1.0` (opaque).
+    pub fn set_opacity(&self, opacity: f32) -> Result<()> {
+        let ex_style = self.ex_style();
+        if opacity >= 1.0 {
+            // Layered windows cost an extra composition pass
+            if ex_style.contains(WS_EX_LAYERED) {
+                self.set_ex_style(ex_style & !WS_EX_LAYERED)?;
+            }
+            return Ok(());
+        }
+        if !ex_style.contains(WS_EX_LAYERED) {
+            self.set_ex_style(ex_style | WS_EX_LAYERED)?;
+        }
+        let alpha = (opacity.max(0.0) * 255.0).round() as u8;
+        unsafe { SetLayeredWindowAttributes(self.hwnd, COLORREF(0), alpha, LWA_ALPHA)? };
+        Ok(())
+    }
+
+    pub fn opacity(&self) -> f32 {
+        if !self.ex_style().contains(WS_EX_LAYERED) {
+            return 1.0;
+        }
+        let mut alpha = 255;
+        let mut flags = LAYERED_WINDOW_ATTRIBUTES_FLAGS::default();
+        let result = unsafe {
+            GetLayeredWindowAttributes(self.hwnd, None, Some(&mut alpha), Some(&mut flags))
+        };
+        if result.is_err() || !flags.contains(LWA_ALPHA) {
+            return 1.0;
+        }
+        alpha as f32 / 255.0
+    }
+
+    /// Switches per-pixel alpha on or off, see
+    /// [`WindowBuilder::with_transparent`].
+    pub fn set_transparent(&self, transparent: bool) -> Result<()> {
+        let Some(state) = (unsafe { window_state(self.hwnd) }) else {
+            bail!("Not a paneless window");
+        };
+        set_alpha_composition(self.hwnd, transparent)?;
+        state.transparent.set(transparent);
+        self.request_redraw();
+        Ok(())
+    }
+
+    pub fn is_transparent(&self) -> bool {
+        unsafe { window_state(self.hwnd) }.is_some_and(|state| state.transparent.get())
+    }
+
+    /// Clips the window to the union of `rects`, in coordinates relative to
+    /// the top-left corner of the frame. Input outside of the shape goes to
+    /// the windows behind.
+    pub fn set_shape(&self, rects: &[RECT]) -> Result<()> {
+        unsafe {
+            let region = CreateRectRgn(0, 0, 0, 0);
+            if region.is_invalid() {
+                bail!("Could not create the window shape");
+            }
+            for rect in rects {
+                let part = CreateRectRgnIndirect(rect);
+                if part.is_invalid() {
+                    let _ = DeleteObject(region);
+                    bail!("Could not create the window shape");
+                }
+                let combined = CombineRgn(region, region, part, RGN_OR);
+                let _ = DeleteObject(part);
+                if combined == RGN_ERROR {
+                    let _ = DeleteObject(region);
+                    bail!("Could not create the window shape");
+                }
+            }
+            // The system owns the region from now on
+            if SetWindowRgn(self.hwnd, region, true) == 0 {
+                let _ = DeleteObject(region);
+                bail!("Could not set the window shape");
+            }
+        }
+        Ok(())
+    }
+
+    /// Shapes the window after the pixels of a `width` by `height` RGBA
+    /// image whose alpha is at least half opaque.
+    pub fn set_shape_from_alpha(&self, width: u32, height: u32, rgba: &[u8]) -> Result<()> {
+        if rgba.len() != width as usize * height as usize * 4 {
+            bail!(
+                "Expected {} bytes of RGBA for a {}x{} image, got {}",
+                width as usize * height as usize * 4,
+                width,
+                height,
+                rgba.len()
+            );
+        }
+        self.set_shape(&opaque_runs(width, height, rgba))
+    }
+
+    /// Goes back to the plain rectangular window.
+    pub fn clear_shape(&self) {
+        unsafe {
+            SetWindowRgn(self.hwnd, HRGN::default(), true);
+        }
+    }
+
+    /// Flashes the window to notify the user while it is in the background,
+    /// `None` stops flashing. Does nothing for the foreground window.
+    pub fn request_user_attention(&self, request_type: Option<UserAttentionType>) {
+        let (flags, count) = match request_type {
+            Some(UserAttentionType::Critical) => (FLASHW_ALL | FLASHW_TIMERNOFG, u32::MAX),
+            Some(UserAttentionType::Informational) => (FLASHW_TRAY | FLASHW_TIMERNOFG, 0),
+            None => (FLASHW_STOP, 0),
+        };
+        let info = FLASHWINFO {
+            cbSize: std::mem::size_of::<FLASHWINFO>() as u32,
+            hwnd: self.hwnd,
+            dwFlags: flags,
+            uCount: count,
+            dwTimeout: 0,
+        };
+        unsafe {
+            let _ = FlashWindowEx(&info);
+        }
+    }
+
+    /// Shows or hides the window, e.g. to reveal one built with
+    /// [`WindowBuilder::with_visible`]`(false)` once its first frame is ready.
+    pub fn set_visible(&self, visible: bool) {
+        let n_cmd_show = if visible { SW_SHOW } else { SW_HIDE };
+        unsafe {
+            let _ = ShowWindow(self.hwnd, n_cmd_show);
+        }
+    }
+
+    /// Shows and activates the window with `animation`, e.g. a tray popup
+    /// sliding up from the taskbar. Blocks for `duration`.
+    pub fn show_animated(&self, animation: Animation, duration: Duration) -> Result<()> {
+        self.animate(animation.flags() | AW_ACTIVATE, duration)
+    }
+
+    /// Hides the window with `animation`. Blocks for `duration`.
+    pub fn hide_animated(&self, animation: Animation, duration: Duration) -> Result<()> {
+        self.animate(animation.flags() | AW_HIDE, duration)
+    }
+
+    fn animate(&self, flags: ANIMATE_WINDOW_FLAGS, duration: Duration) -> Result<()> {
+        let millis = duration.as_millis().try_into().unwrap_or(u32::MAX);
+        unsafe { AnimateWindow(self.hwnd, millis, flags)? };
+        Ok(())
+    }
+
+    pub fn is_visible(&self) -> bool {
+        unsafe { IsWindowVisible(self.hwnd) }.as_bool()
+    }
+
+    /// Sets the icon of the title bar, taskbar button and Alt+Tab, `None`
+    /// goes back to the class icon.
+    pub fn set_icon(&self, icon: Option<&Icon>) {
+        let hicon = icon.map(Icon::hicon).unwrap_or_default();
+        unsafe {
+            for size in [ICON_BIG, ICON_SMALL] {
+                SendMessageW(
+                    self.hwnd,
+                    WM_SETICON,
+                    WPARAM(size as usize),
+                    LPARAM(hicon.0 as isize),
+                );
+            }
+            if let Some(state) = window_state(self.hwnd) {
+                *state.icon.borrow_mut() = icon.cloned();
+            }
+        }
+    }
+
+    /// Adds or removes the taskbar button and Alt+Tab entry, see
+    /// [`WindowBuilder::with_skip_taskbar`].
+    pub fn set_skip_taskbar(&self, skip_taskbar: bool) -> Result<()> {
+        let ex_style = self.ex_style();
+        let ex_style = if skip_taskbar {
+            (ex_style | WS_EX_TOOLWINDOW) & !WS_EX_APPWINDOW
+        } else {
+            ex_style & !WS_EX_TOOLWINDOW
+        };
+        // The taskbar only notices the change when the window is shown again
+        let visible = self.is_visible();
+        if visible {
+            self.set_visible(false);
+        }
+        self.set_ex_style(ex_style)?;
+        if visible {
+            self.set_visible(true);
+        }
+        Ok(())
+    }
+
+    /// The window this one is a child of, see [`WindowBuilder::with_parent`].
+    pub fn parent(&self) -> Option<WindowsWindow> {
+        if !self.style().contains(WS_CHILD) {
+            return None;
+        }
+        let parent = unsafe { GetAncestor(self.hwnd, GA_PARENT) };
+        (!parent.is_invalid()).then(|| Self::from_hwnd(parent))
+    }
+
+    /// Direct children of this window.
+    pub fn children(&self) -> Vec<WindowsWindow> {
+        let mut children = Vec::new();
+        let mut child = unsafe { GetWindow(self.hwnd, GW_CHILD) };
+        while let Ok(hwnd) = child {
+            children.push(Self::from_hwnd(hwnd));
+            child = unsafe { GetWindow(hwnd, GW_HWNDNEXT) };
+        }
+        children
+    }
+
+    /// See [`WindowBuilder::with_fill_parent`].
+    pub fn set_fill_parent(&self, fill_parent: bool) -> Result<()> {
+        let Some(state) = (unsafe { window_state(self.hwnd) }) else {
+            bail!("Not a paneless window");
+        };
+        state.fill_parent.set(fill_parent);
+        if let Some(parent) = self.parent().filter(|_| fill_parent) {
+            let (width, height) = parent.inner_size()?;
+            self.fill(width, height);
+        }
+        Ok(())
+    }
+
+    fn fill(&self, width: u32, height: u32) {
+        unsafe {
+            let _ = MoveWindow(self.hwnd, 0, 0, width as i32, height as i32, true);
+        }
+    }
+
+    #[cfg_attr(
+        not(any(feature = "d2d", feature = "d3d11", feature = "d3d12")),
+        allow(dead_code)
+    )]
+    pub(crate) fn add_resize_target(&self, target: Weak<dyn ResizeTarget>) {
+        if let Some(state) = unsafe { window_state(self.hwnd) } {
+            state.resize_targets.borrow_mut().push(target);
+        }
+    }
+
+    fn resize_targets(&self, width: u32, height: u32) {
+        let Some(state) = (unsafe { window_state(self.hwnd) }) else {
+            return;
+        };
+        let targets: Vec<_> = {
+            let mut targets = state.resize_targets.borrow_mut();
+            targets.retain(|target| target.strong_count() > 0);
+            targets.iter().filter_map(Weak::upgrade).collect()
+        };
+        // Outside the borrow, resizing may send messages
+        for target in targets {
+            target.resize(width, height);
+        }
+    }
+
+    /// Converts a point from this window's client coordinates to screen
+    /// coordinates.
+    pub fn client_to_screen(&self, x: i32, y: i32) -> (i32, i32) {
+        let mut point = POINT { x, y };
+        unsafe {
+            let _ = ClientToScreen(self.hwnd, &mut point);
+        }
+        (point.x, point.y)
+    }
+
+    /// Converts a point from screen coordinates to this window's client
+    /// coordinates.
+    pub fn screen_to_client(&self, x: i32, y: i32) -> (i32, i32) {
+        let mut point = POINT { x, y };
+        unsafe {
+            let _ = ScreenToClient(self.hwnd, &mut point);
+        }
+        (point.x, point.y)
+    }
+
+    /// Converts a point from this window's client coordinates to those of
+    /// `other`, e.g. from a child to its parent.
+    pub fn map_point_to(&self, other: &WindowsWindow, x: i32, y: i32) -> (i32, i32) {
+        let mut points = [POINT { x, y }];
+        unsafe {
+            MapWindowPoints(self.hwnd, other.hwnd, &mut points);
+        }
+        (points[0].x, points[0].y)
+    }
+
+    /// Starts moving the window with the mouse as if its title bar had been
+    /// grabbed. Call it on a left button press in the client area, e.g. over
+    /// a custom-drawn title bar; returns once the button is released.
+    pub fn drag_window(&self) -> Result<()> {
+        self.begin_frame_drag(HTCAPTION)
+    }
+
+    /// Starts resizing the window with the mouse from the edge or corner
+    /// `direction`, like [`WindowsWindow::drag_window`] does for moving.
+    /// Lets undecorated windows offer their own resize grips.
+    pub fn drag_resize_window(&self, direction: ResizeDirection) -> Result<()> {
+        self.begin_frame_drag(direction.hit_test_code())
+    }
+
+    /// Hands the pressed mouse button over to the frame part `hit`.
+    fn begin_frame_drag(&self, hit: u32) -> Result<()> {
+        let mut cursor = POINT::default();
+        unsafe {
+            GetCursorPos(&mut cursor)?;
+            // The press started the client area's own capture
+            let _ = ReleaseCapture();
+            let position = ((cursor.y as u16 as isize) << 16) | cursor.x as u16 as isize;
+            SendMessageW(
+                self.hwnd,
+                WM_NCLBUTTONDOWN,
+                WPARAM(hit as usize),
+                LPARAM(position),
+            );
+        }
+        Ok(())
+    }
+
+    /// Sends all mouse input to the window, even outside of it, until
+    /// released, e.g. to keep dragging a slider. The capture is lost when
+    /// another window takes it or the user switches applications, which
+    /// delivers [`WindowEvent::CaptureLost`].
+    pub fn set_capture(&self, capture: bool) {
+        unsafe {
+            if capture {
+                SetCapture(self.hwnd);
+            } else if self.has_capture() {
+                let _ = ReleaseCapture();
+            }
+        }
+    }
+
+    pub fn has_capture(&self) -> bool {
+        unsafe { GetCapture() == self.hwnd }
+    }
+
+    pub fn minimize(&self) {
+        unsafe {
+            let _ = ShowWindow(self.hwnd, SW_MINIMIZE);
+        }
+    }
+
+    pub fn maximize(&self) {
+        unsafe {
+            let _ = ShowWindow(self.hwnd, SW_MAXIMIZE);
+        }
+    }
+
+    /// Brings a minimized or maximized window back to its normal size and
+    /// position.
+    pub fn restore(&self) {
+        unsafe {
+            let _ = ShowWindow(self.hwnd, SW_RESTORE);
+        }
+    }
+
+    pub fn is_minimized(&self) -> bool {
+        unsafe { IsIconic(self.hwnd) }.as_bool()
+    }
+
+    pub fn is_maximized(&self) -> bool {
+        unsafe { IsZoomed(self.hwnd) }.as_bool()
+    }
+
+    /// Keeps the window open after a [`WindowEvent::CloseRequested`], e.g. to
+    /// ask about unsaved changes first. Only has an effect while handling
+    /// that event.
+    pub fn veto_close(&self) {
+        if let Some(state) = unsafe { window_state(self.hwnd) } {
+            state.close_vetoed.set(true);
+        }
+    }
+
+    /// Destroys the window right away, without a
+    /// [`WindowEvent::CloseRequested`] that could veto it.
+    pub fn close(&self) -> Result<()> {
+        self.end_modal();
+        unsafe { DestroyWindow(self.hwnd)? };
+        Ok(())
+    }
+
+    /// The window owning this one, see [`WindowBuilder::with_owner`].
+    pub fn owner(&self) -> Option<WindowsWindow> {
+        unsafe { GetWindow(self.hwnd, GW_OWNER) }
+            .ok()
+            .map(Self::from_hwnd)
+    }
+
+    /// Lets the window take mouse and keyboard input again, or rejects it,
+    /// e.g. while a long operation is busy. A disabled window still paints
+    /// and receives events from the application.
+    pub fn set_enabled(&self, enabled: bool) {
+        unsafe {
+            let _ = EnableWindow(self.hwnd, enabled);
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        unsafe { IsWindowEnabled(self.hwnd) }.as_bool()
+    }
+
+    /// Turns off the auto-repeat of held keys, for games that only care
+    /// about presses and releases. Typed characters still repeat.
+    pub fn set_key_repeat(&self, repeat: bool) -> Result<()> {
+        let Some(state) = (unsafe { window_state(self.hwnd) }) else {
+            bail!("Not a paneless window");
+        };
+        state.suppress_key_repeat.set(!repeat);
+        Ok(())
+    }
+
+    /// The modifiers held down as of the last keyboard message, none while
+    /// the window doesn't have the focus.
+    pub fn modifiers(&self) -> ModifiersState {
+        unsafe { window_state(self.hwnd) }
+            .map(|state| state.modifiers.get())
+            .unwrap_or_default()
+    }
+
+    /// Whether `key` is held down as of the last keyboard message, `false`
+    /// while the window doesn't have the focus. See [`keyboard_snapshot`]
+    /// to query several keys.
+    pub fn key_state(&self, key: KeyCode) -> bool {
+        (unsafe { GetFocus() }) == self.hwnd && keyboard_snapshot().is_pressed(key)
+    }
+
+    /// Shows an owned window as a modal dialog: its owner is disabled until
+    /// the dialog closes, then enabled and activated again. Returns right
+    /// away, the event loop keeps running meanwhile.
+    pub fn show_modal(&self) -> Result<()> {
+        let Some(owner) = self.owner() else {
+            bail!("Only owned windows can be modal");
+        };
+        let Some(state) = (unsafe { window_state(self.hwnd) }) else {
+            bail!("Not a paneless window");
+        };
+        state.modal_owner.set(Some(owner.hwnd));
+        unsafe {
+            let _ = EnableWindow(owner.hwnd, false);
+            let _ = ShowWindow(self.hwnd, SW_SHOW);
+            let _ = SetForegroundWindow(self.hwnd);
+        }
+        Ok(())
+    }
+
+    // The owner must be enabled before the dialog is destroyed, or Windows
+    // activates some other application instead.
+    fn end_modal(&self) {
+        let owner = unsafe { window_state(self.hwnd) }.and_then(|state| state.modal_owner.take());
+        if let Some(owner) = owner {
+            unsafe {
+                let _ = EnableWindow(owner, true);
+                let _ = SetForegroundWindow(owner);
+            }
+        }
+    }
+
+    /// Starts (or restarts) a timer delivering [`WindowEvent::Timer`] with
+    /// `id` every `interval`, clamped to what `SetTimer` supports.
+    pub fn set_timer(&self, id: usize, interval: Duration) -> Result<()> {
+        if id == MODAL_LOOP_TIMER_ID {
+            bail!("Timer id {:#x} is reserved by paneless", id);
+        }
+        let millis = interval
+            .as_millis()
+            .clamp(USER_TIMER_MINIMUM as u128, USER_TIMER_MAXIMUM as u128)
+            as u32;
+        if unsafe { SetTimer(self.hwnd, id, millis, None) } == 0 {
+            let last_error = unsafe { GetLastError() };
+            bail!("Could not set timer {}, error code: {:?}", id, last_error);
+        }
+        Ok(())
+    }
+
+    /// Stops the timer started with `id`.
+    pub fn cancel_timer(&self, id: usize) -> Result<()> {
+        unsafe { KillTimer(self.hwnd, id)? };
+        Ok(())
+    }
+
+    /// Invalidates the whole client area so the window receives a
+    /// [`WindowEvent::RedrawRequested`] once the message queue is empty.
+    pub fn request_redraw(&self) {
+        unsafe {
+            let _ = InvalidateRect(self.hwnd, None, false);
+        }
+    }
+
+    /// Registers `class` unless it already is, returning its name for
+    /// `CreateWindowExW`.
+    fn register_class(h_instance: HMODULE, class: &WindowClass) -> Result<Vec<u16>> {
+        let class_name = str_to_wstr(&class.name);
+        let icon = class.icon.as_ref().map(Icon::hicon).unwrap_or_default();
+
+        let mut existing = WNDCLASSEXW {
+            cbSize: std::mem::size_of::<WNDCLASSEXW>() as u32,
+            ..Default::default()
+        };
+        if unsafe { GetClassInfoExW(h_instance, PCWSTR(class_name.as_ptr()), &mut existing) }
+            .is_ok()
+        {
+            // Registered before, by paneless or by someone else
+            let ours = existing.lpfnWndProc.map(|proc| proc as usize)
+                == Some(Self::window_procedure as *const () as usize);
+            if !ours {
+                bail!(
+                    "The window class {:?} belongs to another window procedure",
+                    class.name
+                );
+            }
+            // Icons are loaded anew for each builder, the first one stays
+            if existing.style != class.style
+                || existing.hbrBackground != class.background.unwrap_or_default()
+            {
+                bail!(
+                    "The window class {:?} is already registered with other attributes",
+                    class.name
+                );
+            }
+            return Ok(class_name);
+        }
+
+        let wc = WNDCLASSEXW {
+            cbSize: std::mem::size_of::<WNDCLASSEXW>() as u32,
+            style: class.style,
+            lpfnWndProc: Some(Self::window_procedure),
+            hInstance: h_instance.into(),
+            hIcon: icon,
+            // Set on WM_SETCURSOR, see WindowsWindow::set_cursor
+            hCursor: HCURSOR::default(),
+            hbrBackground: class.background.unwrap_or_default(),
+            lpszClassName: PCWSTR(class_name.as_ptr()),
+            ..Default::default()
+        };
+        if unsafe { RegisterClassExW(&wc) } == 0 {
+            let last_error = unsafe { GetLastError() };
+            // Another thread may have just registered it
+            if last_error != ERROR_CLASS_ALREADY_EXISTS {
+                bail!(
+                    "Could not register the window class, error code: {:?}",
+                    last_error
+                );
+            }
+            return Ok(class_name);
+        }
+        // Classes live until the process exits, and so must their icon
+        std::mem::forget(class.icon.clone());
+
+        Ok(class_name)
+    }
+
+    fn init_instance(
+        h_instance: HMODULE,
+        class_name: PCWSTR,
+        window_title: PCWSTR,
+        builder: &WindowBuilder,
+    ) -> Result<HWND> {
+        let (style, ex_style) = builder.styles();
+
+        // The builder sizes the client area, CreateWindowExW the outer frame
+        let (width, height) = match builder.size {
+            Some((width, height)) => outer_size_for(width, height, style, ex_style)?,
+            None => (CW_USEDEFAULT, CW_USEDEFAULT),
+        };
+        let (x, y) = builder.position.unwrap_or((CW_USEDEFAULT, CW_USEDEFAULT));
+
+        // Prepare window state, reclaimed on WM_NCDESTROY
+        let state = WindowState {
+            transparent: Cell::new(builder.transparent),
+            resizable: Cell::new(builder.resizable),
+            decorations: Cell::new(builder.decorations),
+            fill_parent: Cell::new(builder.fill_parent),
+            theme: Cell::new(builder.theme),
+            system_theme: Cell::new(Some(system_theme())),
+            ..Default::default()
+        };
+        let lparam: *mut WindowState = Box::into_raw(Box::new(state));
+
+        // Create window of class wc and get Handle
+        let hwnd = unsafe {
+            CreateWindowExW(
+                ex_style,
+                class_name,
+                window_title,
+                style,
+                x,
+                y,
+                width,
+                height,
+                builder.parent.or(builder.owner).unwrap_or_default(),
+                HMENU::default(),
+                h_instance,
+                Some(lparam.cast()),
+            )?
+        };
+        // Don't leave a half set up window behind when a step fails
+        let guard = DestroyGuard(hwnd);
+
+        if let Some(icon) = &builder.icon {
+            Self::from_hwnd(hwnd).set_icon(Some(icon));
+        }
+        if builder.transparent {
+            set_alpha_composition(hwnd, true)?;
+        }
+        // Older systems don't know the attribute
+        let _ = Self::from_hwnd(hwnd).apply_theme(builder.theme.unwrap_or_else(system_theme));
+        if builder.centered {
+            Self::from_hwnd(hwnd).center()?;
+        }
+        if let Some(parent) = builder.parent {
+            // Keep the parent from painting over its children
+            let parent = Self::from_hwnd(parent);
+            let style = parent.style();
+            if !style.contains(WS_CLIPCHILDREN) {
+                parent.set_style(style | WS_CLIPCHILDREN)?;
+            }
+            if builder.fill_parent {
+                let (width, height) = parent.inner_size()?;
+                Self::from_hwnd(hwnd).fill(width, height);
+            }
+        }
+
+        // Show created window
+        if builder.visible {
+            let n_cmd_show = if builder.maximized {
+                SW_SHOWMAXIMIZED
+            } else {
+                SW_SHOW
+            };
+            unsafe {
+                let _ = ShowWindow(hwnd, n_cmd_show);
+                UpdateWindow(hwnd).ok()?;
+            }
+        }
+        std::mem::forget(guard);
+        Ok(hwnd)
+    }
+
+    pub unsafe extern "system" fn window_procedure(
+        hwnd: HWND,
+        msg: c_uint,
+        w_param: WPARAM,
+        l_param: LPARAM,
+    ) -> LRESULT {
+        #[cfg(feature = "tracing")]
+        let _span = crate::utils::trace::message_span(hwnd, msg);
+
+        if let Some(state) = window_state(hwnd) {
+            // The state must still be freed on WM_NCDESTROY
+            match state.call_msg_hook(hwnd, msg, w_param, l_param) {
+                Some(result) if msg != WM_NCDESTROY => return result,
+                _ => (),
+            }
+        }
+
+        if msg == WM_MOUSEMOVE && Self::from_hwnd(hwnd).track_cursor() {
+            dispatch_window_event(hwnd, WindowEvent::CursorEntered);
+        }
+
+        if msg == WM_SIZE && w_param.0 as u32 != SIZE_MINIMIZED {
+            let (width, height) = (loword(l_param.0) as u32, hiword(l_param.0) as u32);
+            Self::from_hwnd(hwnd).resize_targets(width, height);
+        }
+
+        if let Some(event) = translate_to_event(msg, w_param, l_param) {
+            let suppressed = matches!(event, WindowEvent::KeyboardInput { repeat: true, .. })
+                && window_state(hwnd).is_some_and(|state| state.suppress_key_repeat.get());
+            if !suppressed {
+                dispatch_window_event(hwnd, event);
+            }
+        }
+
+        if matches!(msg, WM_MOVE | WM_SIZE | WM_SETFOCUS | WM_KILLFOCUS) {
+            Self::from_hwnd(hwnd).update_cursor_clip();
+        }
+        if matches!(msg, WM_SETFOCUS | WM_KILLFOCUS | WM_DESTROY) {
+            Self::from_hwnd(hwnd).update_accessibility_shortcuts(msg == WM_SETFOCUS);
+        }
+
+        match msg {
+            WM_NCCREATE => {
+                let createstruct: *mut CREATESTRUCTW = l_param.0 as *mut _;
+                if createstruct.is_null() {
+                    return LRESULT(0);
+                }
+                //Set Window Title
+                SetWindowTextW(hwnd, (*createstruct).lpszName).unwrap();
+
+                let ptr: *mut WindowState = (*createstruct).lpCreateParams.cast();
+                let stored = set_window_userdata::<WindowState>(hwnd, ptr).is_ok();
+                if stored {
+                    LIVE_WINDOWS.with(|count| count.set(count.get() + 1));
+                }
+                return LRESULT(stored as isize);
+            }
+            //WM_CREATE => (),
+            WM_CLOSE => {
+                // CloseRequested was just dispatched, the handler may have
+                // vetoed it.
+                let vetoed = window_state(hwnd).is_some_and(|state| state.close_vetoed.take());
+                if !vetoed {
+                    Self::from_hwnd(hwnd).end_modal();
+                    let _ = DestroyWindow(hwnd);
+                }
+            }
+            // Destroyed along with its owner or by DestroyWindow directly
+            WM_DESTROY => {
+                Self::from_hwnd(hwnd).end_modal();
+                #[cfg(feature = "gl")]
+                Self::from_hwnd(hwnd).destroy_gl_context();
+            }
+            WM_NCDESTROY => {
+                match get_window_userdata::<WindowState>(hwnd) {
+                    Ok(ptr) if !ptr.is_null() => {
+                        let _ = Box::from_raw(ptr);
+                    }
+                    Ok(_) => (),
+                    Err(e) => {
+                        log_error!(
+                            "Error while getting the userdata ptr to clean it up: {:?}",
+                            e
+                        );
+                    }
+                }
+                // Quit once the last window of the thread is gone
+                let remaining = LIVE_WINDOWS.with(|count| {
+                    count.set(count.get().saturating_sub(1));
+                    count.get()
+                });
+                if remaining == 0 {
+                    post_quit_message(0);
+                }
+            }
+            // Dragging the frame runs a modal loop inside DefWindowProc, a
+            // timer keeps our loop iterating meanwhile.
+            WM_ENTERSIZEMOVE => {
+                SetTimer(hwnd, MODAL_LOOP_TIMER_ID, USER_TIMER_MINIMUM, None);
+            }
+            WM_EXITSIZEMOVE => {
+                let _ = KillTimer(hwnd, MODAL_LOOP_TIMER_ID);
+            }
+            WM_TIMER if w_param.0 == MODAL_LOOP_TIMER_ID => modal_loop_tick(),
+            WM_SIZE => {
+                if let Some(state) = window_state(hwnd) {
+                    let size_state = w_param.0 as u32;
+                    let previous = state.size_state.replace(size_state);
+                    if let Some(event) = size_state_event(previous, size_state) {
+                        dispatch_window_event(hwnd, event);
+                    }
+                }
+                if w_param.0 as u32 != SIZE_MINIMIZED {
+                    let (width, height) = (loword(l_param.0) as u32, hiword(l_param.0) as u32);
+                    for child in Self::from_hwnd(hwnd).children() {
+                        if window_state(child.hwnd).is_some_and(|state| state.fill_parent.get()) {
+                            child.fill(width, height);
+                        }
+                    }
+                }
+            }
+            WM_NCHITTEST => {
+                let hit = window_state(hwnd).and_then(|state| {
+                    // Screen coordinates, negative on secondary monitors
+                    let x = loword(l_param.0) as i16 as i32;
+                    let y = hiword(l_param.0) as i16 as i32;
+                    let (x, y) = Self::from_hwnd(hwnd).screen_to_client(x, y);
+                    state.call_hit_test(x, y)
+                });
+                return match hit {
+                    Some(hit) => LRESULT(hit.code()),
+                    None => DefWindowProcW(hwnd, msg, w_param, l_param),
+                };
+            }
+            // The system would draw classic buttons over custom ones
+            WM_NCMOUSEMOVE | WM_NCLBUTTONDOWN | WM_NCLBUTTONUP
+                if is_caption_button(w_param.0 as u32)
+                    && window_state(hwnd).is_some_and(WindowState::has_hit_test) =>
+            {
+                let window = Self::from_hwnd(hwnd);
+                let (x, y) = window.screen_to_client(
+                    loword(l_param.0) as i16 as i32,
+                    hiword(l_param.0) as i16 as i32,
+                );
+                match msg {
+                    WM_NCMOUSEMOVE => {
+                        dispatch_window_event(hwnd, WindowEvent::CursorMoved { x, y });
+                        return DefWindowProcW(hwnd, msg, w_param, l_param);
+                    }
+                    WM_NCLBUTTONDOWN => dispatch_window_event(
+                        hwnd,
+                        mouse_input(MouseButton::Left, ElementState::Pressed),
+                    ),
+                    _ => {
+                        dispatch_window_event(
+                            hwnd,
+                            mouse_input(MouseButton::Left, ElementState::Released),
+                        );
+                        match w_param.0 as u32 {
+                            HTMINBUTTON => window.minimize(),
+                            HTMAXBUTTON if window.is_maximized() => window.restore(),
+                            HTMAXBUTTON => window.maximize(),
+                            _ => {
+                                let _ = PostMessageW(hwnd, WM_CLOSE, WPARAM(0), LPARAM(0));
+                            }
+                        }
+                    }
+                }
+            }
+            WM_SETTINGCHANGE => {
+                let area = PCWSTR(l_param.0 as *const u16);
+                if !area.is_null()
+                    && area
+                        .to_string()
+                        .is_ok_and(|area| area == "ImmersiveColorSet")
+                {
+                    if let Some(event) = Self::from_hwnd(hwnd).system_theme_changed() {
+                        dispatch_window_event(hwnd, event);
+                    }
+                }
+                return DefWindowProcW(hwnd, msg, w_param, l_param);
+            }
+            WM_GETMINMAXINFO => {
+                let window = Self::from_hwnd(hwnd);
+                let info = &mut *(l_param.0 as *mut MINMAXINFO);
+                if let Some(state) = window_state(hwnd) {
+                    let (style, ex_style) = (window.style(), window.ex_style());
+                    if let Some((width, height)) = state.min_inner_size.get() {
+                        if let Ok((x, y)) = outer_size_for(width, height, style, ex_style) {
+                            info.ptMinTrackSize = POINT { x, y };
+                        }
+                    }
+                    if let Some((width, height)) = state.max_inner_size.get() {
+                        if let Ok((x, y)) = outer_size_for(width, height, style, ex_style) {
+                            info.ptMaxTrackSize = POINT { x, y };
+                        }
+                    }
+                }
+            }
+            WM_SIZING => {
+                let window = Self::from_hwnd(hwnd);
+                let Some(ratio) = window_state(hwnd).and_then(|state| state.aspect_ratio.get())
+                else {
+                    return DefWindowProcW(hwnd, msg, w_param, l_param);
+                };
+                let rect = &mut *(l_param.0 as *mut RECT);
+                if let Ok(frame) = outer_size_for(0, 0, window.style(), window.ex_style()) {
+                    constrain_aspect_ratio(rect, w_param.0 as u32, ratio, frame);
+                }
+                return LRESULT(1);
+            }
+            WM_KEYDOWN | WM_SYSKEYDOWN | WM_KEYUP | WM_SYSKEYUP | WM_SETFOCUS | WM_KILLFOCUS => {
+                // Nothing is held down for a window without focus
+                let modifiers = if msg == WM_KILLFOCUS {
+                    ModifiersState::default()
+                } else {
+                    ModifiersState::current()
+                };
+                if let Some(state) = window_state(hwnd) {
+                    if state.modifiers.replace(modifiers) != modifiers {
+                        dispatch_window_event(hwnd, WindowEvent::ModifiersChanged(modifiers));
+                    }
+                }
+                return DefWindowProcW(hwnd, msg, w_param, l_param);
+            }
+            WM_IME_STARTCOMPOSITION | WM_IME_COMPOSITION | WM_IME_ENDCOMPOSITION => {
+                let Some(events) = Self::from_hwnd(hwnd).ime_message(msg, l_param) else {
+                    return DefWindowProcW(hwnd, msg, w_param, l_param);
+                };
+                for event in events {
+                    dispatch_window_event(hwnd, event);
+                }
+            }
+            WM_TOUCH => {
+                let Some(events) = Self::from_hwnd(hwnd).touch_message(w_param, l_param) else {
+                    return DefWindowProcW(hwnd, msg, w_param, l_param);
+                };
+                for event in events {
+                    dispatch_window_event(hwnd, event);
+                }
+            }
+            WM_GESTURE => {
+                let Some(events) = Self::from_hwnd(hwnd).gesture_message(l_param) else {
+                    return DefWindowProcW(hwnd, msg, w_param, l_param);
+                };
+                for event in events {
+                    dispatch_window_event(hwnd, event);
+                }
+            }
+            WM_LBUTTONDBLCLK | WM_RBUTTONDBLCLK | WM_MBUTTONDBLCLK | WM_XBUTTONDBLCLK => {
+                let button = match msg {
+                    WM_LBUTTONDBLCLK => MouseButton::Left,
+                    WM_RBUTTONDBLCLK => MouseButton::Right,
+                    WM_MBUTTONDBLCLK => MouseButton::Middle,
+                    _ => extra_button(w_param),
+                };
+                dispatch_window_event(hwnd, WindowEvent::DoubleClick { button });
+                if msg == WM_XBUTTONDBLCLK {
+                    return LRESULT(1);
+                }
+            }
+            // Handled extra buttons return TRUE
+            WM_XBUTTONDOWN | WM_XBUTTONUP => return LRESULT(1),
+            WM_MOUSEWHEEL | WM_MOUSEHWHEEL => {
+                let delta = hiword(w_param.0 as isize) as i16 as i32;
+                let (x, y) = if msg == WM_MOUSEHWHEEL {
+                    (delta, 0)
+                } else {
+                    (0, delta)
+                };
+                let (lines_per_notch, chars_per_notch) = wheel_settings();
+                let delta = ScrollDelta {
+                    x,
+                    y,
+                    lines_per_notch,
+                    chars_per_notch,
+                };
+                dispatch_window_event(hwnd, WindowEvent::MouseWheel(delta));
+            }
+            WM_INPUT => {
+                for event in raw_input_events(HRAWINPUT(l_param.0 as *mut _)) {
+                    dispatch_event(hwnd, event);
+                }
+                return DefWindowProcW(hwnd, msg, w_param, l_param);
+            }
+            // Left to DefWindowProc afterwards, which turns the primary
+            // touch into mouse input
+            WM_POINTERDOWN | WM_POINTERUPDATE | WM_POINTERUP => {
+                if let Some(event) = Self::from_hwnd(hwnd).pointer_event(msg, w_param) {
+                    dispatch_window_event(hwnd, event);
+                }
+                return DefWindowProcW(hwnd, msg, w_param, l_param);
+            }
+            WM_INPUT_DEVICE_CHANGE => {
+                if let Some(event) = device_change_event(w_param, l_param) {
+                    dispatch_event(hwnd, event);
+                }
+            }
+            WM_SETCURSOR
+                if loword(l_param.0) as u32 == HTCLIENT
+                    && Self::from_hwnd(hwnd).set_client_cursor() =>
+            {
+                return LRESULT(1);
+            }
+            WM_MOUSELEAVE => {
+                if let Some(state) = window_state(hwnd) {
+                    state.cursor_inside.set(false);
+                }
+                dispatch_window_event(hwnd, WindowEvent::CursorLeft);
+            }
+            WM_CHAR => {
+                let character = window_state(hwnd)
+                    .and_then(|state| decode_utf16_unit(&state.high_surrogate, w_param.0 as u16));
+                if let Some(character) = character {
+                    dispatch_window_event(hwnd, WindowEvent::ReceivedCharacter(character));
+                }
+            }
+            // Sent by some IMEs and remote input tools with whole code points
+            WM_UNICHAR => {
+                if w_param.0 as u32 == UNICODE_NOCHAR {
+                    return LRESULT(1);
+                }
+                if let Some(character) = char::from_u32(w_param.0 as u32) {
+                    dispatch_window_event(hwnd, WindowEvent::ReceivedCharacter(character));
+                }
+            }
+            WM_ERASEBKGND if window_state(hwnd).is_some_and(|state| state.transparent.get()) => {
+                return LRESULT(1);
+            }
+            WM_PAINT => {
+                // The class brush already erased the background, the
+                // application draws the rest while the paint cycle is open.
+                do_some_painting(hwnd, |painter| {
+                    let state = window_state(hwnd);
+                    if let Some(state) = state {
+                        state.painting.set(Some(painter.cycle()));
+                    }
+                    dispatch_window_event(hwnd, WindowEvent::RedrawRequested);
+                    if let Some(state) = state {
+                        state.painting.set(None);
+                    }
+                    Ok(())
+                })
+                .unwrap_or_else(|e| log_error!("Error during painting: {:?}", e));
+            }
+            _ => return DefWindowProcW(hwnd, msg, w_param, l_param),
+        }
+        LRESULT(0)
+    }
+}
+
+/// Outer size of a window with these styles and a client area of `width` by
+/// `height`.
+fn outer_size_for(
+    width: u32,
+    height: u32,
+    style: WINDOW_STYLE,
+    ex_style: WINDOW_EX_STYLE,
+) -> Result<(i32, i32)> {
+    let mut rect = RECT {
+        left: 0,
+        top: 0,
+        right: width as i32,
+        bottom: height as i32,
+    };
+    unsafe { AdjustWindowRectEx(&mut rect, style, false, ex_style)? };
+    Ok((rect.right - rect.left, rect.bottom - rect.top))
+}
+
+pub(super) fn rect_size(rect: &RECT) -> (u32, u32) {
+    (
+        (rect.right - rect.left).max(0) as u32,
+        (rect.bottom - rect.top).max(0) as u32,
+    )
+}
+
+/// Makes the compositor honor the alpha channel of the client area, or go
+/// back to treating it as opaque.
+fn set_alpha_composition(hwnd: HWND, enabled: bool) -> Result<()> {
+    unsafe {
+        // An empty region enables the alpha channel without blurring anything
+        let region = CreateRectRgn(0, 0, -1, -1);
+        let blur_behind = DWM_BLURBEHIND {
+            dwFlags: DWM_BB_ENABLE | DWM_BB_BLURREGION,
+            fEnable: enabled.into(),
+            hRgnBlur: region,
+            fTransitionOnMaximized: false.into(),
+        };
+        let result = DwmEnableBlurBehindWindow(hwnd, &blur_behind);
+        let _ = DeleteObject(region);
+        result?;
+    }
+    Ok(())
+}
+
+/// Horizontal runs of opaque pixels of an RGBA image, one rect per run.
+fn opaque_runs(width: u32, height: u32, rgba: &[u8]) -> Vec<RECT> {
+    let mut rects = Vec::new();
+    for (y, row) in rgba
+        .chunks_exact(width as usize * 4)
+        .take(height as usize)
+        .enumerate()
+    {
+        let mut start = None;
+        for x in 0..=width as usize {
+            let opaque = x < width as usize && row[x * 4 + 3] >= 0x80;
+            match (opaque, start) {
+                (true, None) => start = Some(x),
+                (false, Some(left)) => {
+                    rects.push(RECT {
+                        left: left as i32,
+                        top: y as i32,
+                        right: x as i32,
+                        bottom: y as i32 + 1,
+                    });
+                    start = None;
+                }
+                _ => (),
+            }
+        }
+    }
+    rects
+}
+
+fn dispatch_window_event(hwnd: HWND, event: WindowEvent) {
+    dispatch_event(
+        hwnd,
+        Event::WindowEvent {
+            window_id: WindowId::from(hwnd),
+            event,
+        },
+    );
+}
+
+fn translate_to_event(msg: u32, w_param: WPARAM, l_param: LPARAM) -> Option<WindowEvent> {
+    let event = match msg {
+        WM_SIZE => WindowEvent::Resized {
+            width: loword(l_param.0) as u32,
+            height: hiword(l_param.0) as u32,
+        },
+        WM_MOVE => WindowEvent::Moved {
+            x: loword(l_param.0) as i16 as i32,
+            y: hiword(l_param.0) as i16 as i32,
+        },
+        WM_CLOSE => WindowEvent::CloseRequested,
+        WM_DESTROY => WindowEvent::Destroyed,
+        WM_TIMER if w_param.0 != MODAL_LOOP_TIMER_ID => WindowEvent::Timer(w_param.0),
+        WM_SETFOCUS => WindowEvent::Focused(true),
+        WM_KILLFOCUS => WindowEvent::Focused(false),
+        WM_ENABLE => WindowEvent::Enabled(w_param.0 != 0),
+        WM_CAPTURECHANGED => WindowEvent::CaptureLost,
+        WM_INPUTLANGCHANGE => {
+            WindowEvent::KeyboardLayoutChanged(KeyboardLayout::from_hkl(HKL(l_param.0 as *mut _)))
+        }
+        WM_KEYDOWN | WM_SYSKEYDOWN | WM_KEYUP | WM_SYSKEYUP => {
+            // Bits 16-23 hold the scancode, bit 24 flags extended keys.
+            let scancode = ((l_param.0 >> 16) & 0x1ff) as u16;
+            let pressed = msg == WM_KEYDOWN || msg == WM_SYSKEYDOWN;
+            WindowEvent::KeyboardInput {
+                physical_key: KeyCode::from_scancode(scancode),
+                logical_key: KeyCode::from_virtual_key(loword(w_param.0 as isize), scancode),
+                state: if pressed {
+                    ElementState::Pressed
+                } else {
+                    ElementState::Released
+                },
+                // Bit 30 is set if the key was already down.
+                repeat: pressed && l_param.0 & (1 << 30) != 0,
+            }
+        }
+        WM_MOUSEMOVE => WindowEvent::CursorMoved {
+            x: loword(l_param.0) as i16 as i32,
+            y: hiword(l_param.0) as i16 as i32,
+        },
+        // The second press of a double click comes as a WM_*BUTTONDBLCLK
+        WM_LBUTTONDOWN | WM_LBUTTONDBLCLK => mouse_input(MouseButton::Left, ElementState::Pressed),
+        WM_LBUTTONUP => mouse_input(MouseButton::Left, ElementState::Released),
+        WM_RBUTTONDOWN | WM_RBUTTONDBLCLK => mouse_input(MouseButton::Right, ElementState::Pressed),
+        WM_RBUTTONUP => mouse_input(MouseButton::Right, ElementState::Released),
+        WM_MBUTTONDOWN | WM_MBUTTONDBLCLK => {
+            mouse_input(MouseButton::Middle, ElementState::Pressed)
+        }
+        WM_MBUTTONUP => mouse_input(MouseButton::Middle, ElementState::Released),
+        WM_XBUTTONDOWN | WM_XBUTTONDBLCLK => {
+            mouse_input(extra_button(w_param), ElementState::Pressed)
+        }
+        WM_XBUTTONUP => mouse_input(extra_button(w_param), ElementState::Released),
+        _ => return None,
+    };
+    Some(event)
+}
+
+/// Event for a change of the WM_SIZE type, `None` for plain resizes.
+fn size_state_event(previous: u32, current: u32) -> Option<WindowEvent> {
+    if previous == current {
+        return None;
+    }
+    match current {
+        SIZE_MINIMIZED => Some(WindowEvent::Minimized),
+        SIZE_MAXIMIZED => Some(WindowEvent::Maximized),
+        SIZE_RESTORED => Some(WindowEvent::Restored),
+        // Other windows being maximized or restored
+        _ => None,
+    }
+}
+
+/// Adjusts the frame rectangle of a WM_SIZING drag so the client area keeps
+/// `ratio`, moving the side opposite to the grabbed one or the one next to
+/// the grabbed corner. `frame` is the size of the frame around the client
+/// area.
+fn constrain_aspect_ratio(rect: &mut RECT, edge: u32, ratio: (u32, u32), frame: (i32, i32)) {
+    let (ratio_width, ratio_height) = (ratio.0 as i64, ratio.1 as i64);
+    let (width, height) = rect_size(rect);
+    let width = (width as i64 - frame.0 as i64).max(0);
+    let height = (height as i64 - frame.1 as i64).max(0);
+    if matches!(edge, WMSZ_TOP | WMSZ_BOTTOM) {
+        let width = height * ratio_width / ratio_height;
+        rect.right = rect.left + width as i32 + frame.0;
+        return;
+    }
+    let height = (width * ratio_height / ratio_width) as i32 + frame.1;
+    if matches!(edge, WMSZ_TOPLEFT | WMSZ_TOPRIGHT) {
+        rect.top = rect.bottom - height;
+    } else {
+        rect.bottom = rect.top + height;
+    }
+}
+
+/// Decodes a UTF-16 code unit sent through WM_CHAR, keeping the first half of
+/// a surrogate pair in `high_surrogate` until the second one arrives.
+fn decode_utf16_unit(high_surrogate: &Cell<Option<u16>>, unit: u16) -> Option<char> {
+    if (0xd800..0xdc00).contains(&unit) {
+        high_surrogate.set(Some(unit));
+        return None;
+    }
+    match high_surrogate.take() {
+        Some(high) => char::decode_utf16([high, unit]).next()?.ok(),
+        None => char::from_u32(unit as u32),
+    }
+}
+
+/// Lines and characters the user wants to scroll per wheel notch, 3 by
+/// default. No lines means a screen per notch.
+fn wheel_settings() -> (Option<u32>, u32) {
+    const WHEEL_PAGESCROLL: u32 = u32::MAX;
+
+    let read = |action| {
+        let mut value = 3u32;
+        let _ = unsafe {
+            SystemParametersInfoW(
+                action,
+                0,
+                Some(&mut value as *mut u32 as *mut c_void),
+                SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+            )
+        };
+        value
+    };
+    let lines = read(SPI_GETWHEELSCROLLLINES);
+    (
+        (lines != WHEEL_PAGESCROLL).then_some(lines),
+        read(SPI_GETWHEELSCROLLCHARS),
+    )
+}
+
+fn is_caption_button(hit: u32) -> bool {
+    matches!(hit, HTMINBUTTON | HTMAXBUTTON | HTCLOSE)
+}
+
+fn mouse_input(button: MouseButton, state: ElementState) -> WindowEvent {
+    WindowEvent::MouseInput { button, state }
+}
+
+/// The extra button of a WM_XBUTTON* message.
+fn extra_button(w_param: WPARAM) -> MouseButton {
+    if hiword(w_param.0 as isize) == XBUTTON2 {
+        MouseButton::Forward
+    } else {
+        MouseButton::Back
+    }
+}
+
+fn loword(value: isize) -> u16 {
+    (value & 0xffff) as u16
+}
+
+fn hiword(value: isize) -> u16 {
+    ((value >> 16) & 0xffff) as u16
+}
+
+pub fn get_instance_handle() -> HMODULE {
+    extern "C" {
+        static __ImageBase: IMAGE_DOS_HEADER;
+    }
+
+    HMODULE(unsafe { &__ImageBase as *const _ as *mut c_void })
+}
+
+pub fn load_default_cursor(cursor: PCWSTR) -> Result<HCURSOR> {
+    let hcursor = unsafe { LoadCursorW(HINSTANCE::default(), cursor).unwrap() };
+    if hcursor.is_invalid() {
+        bail!("Failed to load predefined cursor");
+    } else {
+        Ok(hcursor)
+    }
+}
+
+pub fn get_next_message() -> Result<MSG> {
+    let mut msg = MSG::default();
+    let output = unsafe { GetMessageW(&mut msg, HWND::default(), 0, 0) };
+    if output.0 >= 0 {
+        Ok(msg)
+    } else {
+        bail!("Failed getting next message")
+    }
+}
+
+pub fn translte_message(msg: &MSG) -> Result<bool> {
+    let res = unsafe { TranslateMessage(msg) };
+    match res.ok() {
+        Ok(_) => Ok(0 != res.0),
+        Err(err) => Err(err.into()),
+    }
+}
+
+pub unsafe fn set_window_userdata<T>(hwnd: HWND, ptr: *mut T) -> Result<*mut T, WIN32_ERROR> {
+    SetLastError(WIN32_ERROR(0));
+    let out = SetWindowLongPtrW(hwnd, GWLP_USERDATA, ptr as isize);
+    if out == 0 {
+        let last_error = GetLastError();
+        if last_error.0 != 0 {
+            Err(last_error)
+        } else {
+            Ok(out as *mut T)
+        }
+    } else {
+        Ok(out as *mut T)
+    }
+}
+
+pub unsafe fn get_window_userdata<T>(hwnd: HWND) -> Result<*mut T, WIN32_ERROR> {
+    SetLastError(WIN32_ERROR(0));
+    let out = GetWindowLongPtrW(hwnd, GWLP_USERDATA);
+    if out == 0 {
+        let last_error = GetLastError();
+        if last_error.0 != 0 {
+            Err(last_error)
+        } else {
+            Ok(out as *mut T)
+        }
+    } else {
+        Ok(out as *mut T)
+    }
+}
+
+pub fn post_quit_message(exit_code: i32) {
+    unsafe {
+        PostQuitMessage(exit_code);
+    }
+}
+
+pub unsafe fn begin_paint(hwnd: HWND) -> Result<(HDC, PAINTSTRUCT), WIN32_ERROR> {
+    let mut ps = PAINTSTRUCT::default();
+    let hdc = BeginPaint(hwnd, &mut ps);
+    if hdc.is_invalid() {
+        Err(GetLastError())
+    } else {
+        Ok((hdc, ps))
+    }
+}
+
+pub fn sys_color_brush(color: SYS_COLOR_INDEX) -> HBRUSH {
+    // System color brushes are passed as the color index plus one
+    HBRUSH((color.0 + 1) as *mut c_void)
+}
+
+pub unsafe fn end_paint(hwnd: HWND, ps: &PAINTSTRUCT) {
+    EndPaint(hwnd, ps).unwrap();
+}
+
+pub unsafe fn do_some_painting<F, T>(hwnd: HWND, f: F) -> Result<T, WIN32_ERROR>
+where
+    F: FnOnce(&Painter) -> Result<T, WIN32_ERROR>,
+{
+    let (hdc, ps) = begin_paint(hwnd)?;
+    let output = f(&Painter::new(hdc, ps.fErase.as_bool(), ps.rcPaint));
+    end_paint(hwnd, &ps);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn translates_negative_move_coordinates() {
+        let l_param = LPARAM((((-20_i16) as u16 as isize) << 16) | ((-10_i16) as u16 as isize));
+        assert_eq!(
+            translate_to_event(WM_MOVE, WPARAM(0), l_param),
+            Some(WindowEvent::Moved { x: -10, y: -20 })
+        );
+    }
+
+    #[test]
+    fn translates_key_state() {
+        let event = translate_to_event(WM_KEYUP, WPARAM(0x41), LPARAM(0x001e_0001));
+        assert_eq!(
+            event,
+            Some(WindowEvent::KeyboardInput {
+                physical_key: KeyCode::KeyA,
+                logical_key: KeyCode::KeyA,
+                state: ElementState::Released,
+                repeat: false,
+            })
+        );
+        let event = translate_to_event(WM_KEYDOWN, WPARAM(0x41), LPARAM(0x401e_0001));
+        assert!(matches!(
+            event,
+            Some(WindowEvent::KeyboardInput { repeat: true, .. })
+        ));
+    }
+
+    #[test]
+    fn reports_size_state_changes_only() {
+        assert_eq!(
+            size_state_event(SIZE_RESTORED, SIZE_MAXIMIZED),
+            Some(WindowEvent::Maximized)
+        );
+        assert_eq!(
+            size_state_event(SIZE_MINIMIZED, SIZE_RESTORED),
+            Some(WindowEvent::Restored)
+        );
+        assert_eq!(size_state_event(SIZE_RESTORED, SIZE_RESTORED), None);
+    }
+
+    #[test]
+    fn shapes_opaque_pixel_runs() {
+        #[rustfmt::skip]
+        let rgba = [
+            0, 0, 0, 0xff,  0, 0, 0, 0xff,  0, 0, 0, 0,
+            0, 0, 0, 0,     0, 0, 0, 0x80,  0, 0, 0, 0xff,
+        ];
+        let rect = |left, top, right| RECT {
+            left,
+            top,
+            right,
+            bottom: top + 1,
+        };
+        assert_eq!(opaque_runs(3, 2, &rgba), [rect(0, 0, 2), rect(1, 1, 3)]);
+    }
+
+    #[test]
+    fn keeps_the_aspect_ratio_while_sizing() {
+        let rect = |left, top, right, bottom| RECT {
+            left,
+            top,
+            right,
+            bottom,
+        };
+        let mut dragged = rect(100, 100, 420, 200);
+        constrain_aspect_ratio(&mut dragged, WMSZ_RIGHT, (16, 9), (20, 40));
+        assert_eq!(dragged, rect(100, 100, 420, 308));
+
+        let mut dragged = rect(100, 100, 420, 200);
+        constrain_aspect_ratio(&mut dragged, WMSZ_TOPLEFT, (16, 9), (20, 40));
+        assert_eq!(dragged, rect(100, -8, 420, 200));
+
+        let mut dragged = rect(100, 100, 420, 280);
+        constrain_aspect_ratio(&mut dragged, WMSZ_BOTTOM, (16, 9), (20, 40));
+        assert_eq!(dragged, rect(100, 100, 368, 280));
+    }
+
+    #[test]
+    fn translates_extra_buttons() {
+        assert_eq!(
+            translate_to_event(WM_XBUTTONUP, WPARAM(0x0002_0000), LPARAM(0)),
+            Some(mouse_input(MouseButton::Forward, ElementState::Released))
+        );
+    }
+
+    #[test]
+    fn assembles_surrogate_pairs() {
+        let high_surrogate = Cell::new(None);
+        assert_eq!(decode_utf16_unit(&high_surrogate, 'é' as u16), Some('é'));
+        assert_eq!(decode_utf16_unit(&high_surrogate, 0xd83d), None);
+        assert_eq!(decode_utf16_unit(&high_surrogate, 0xde00), Some('😀'));
+        // A lone low surrogate is dropped
+        assert_eq!(decode_utf16_unit(&high_surrogate, 0xde00), None);
+    }
+
+    #[test]
+    fn hides_the_modal_loop_timer() {
+        assert_eq!(
+            translate_to_event(WM_TIMER, WPARAM(7), LPARAM(0)),
+            Some(WindowEvent::Timer(7))
+        );
+        assert_eq!(
+            translate_to_event(WM_TIMER, WPARAM(MODAL_LOOP_TIMER_ID), LPARAM(0)),
+            None
+        );
+    }
+}